@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+use arc_swap::ArcSwapOption;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Blocks a blockhash stays valid for after the block that produced it
+const MAX_PROCESSING_AGE: u64 = 150;
+
+/// First wait before reconnecting to Geyser; doubles up to `MAX_RECONNECT_DELAY`
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A recent blockhash and the last block height it can be included at
+#[derive(Debug, Clone, Copy)]
+pub struct LatestBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+/// Latest blockhash kept fresh by a background task and read without locking
+///
+/// Updates come from the Geyser block-meta stream when configured, otherwise
+/// from polling `getLatestBlockhash`. A dropped or silent stream is bridged by
+/// polling and reconnected with backoff. The task also marks the cache stale,
+/// so reads never check ages themselves.
+pub struct BlockhashCache {
+    current: ArcSwapOption<LatestBlockhash>,
+    stale_after: Duration,
+    stale: AtomicBool,
+}
+
+impl BlockhashCache {
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            current: ArcSwapOption::empty(),
+            stale_after,
+            stale: AtomicBool::new(false),
+        }
+    }
+
+    fn store(&self, blockhash: Hash, last_valid_block_height: u64) {
+        self.current.store(Some(Arc::new(LatestBlockhash {
+            blockhash,
+            last_valid_block_height,
+            fetched_at: Instant::now(),
+        })));
+        if self.stale.swap(false, Ordering::Relaxed) {
+            log::info!("Blockhash cache is fresh again");
+        }
+    }
+
+    /// The cached blockhash, or `None` if there is none yet or it is stale
+    pub fn get(&self) -> Option<LatestBlockhash> {
+        if self.stale.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.current.load_full().map(|latest| *latest)
+    }
+
+    /// Mark the cache stale once nothing has been stored for `stale_after`,
+    /// counting from `since` at the earliest; returns whether it is stale
+    fn check_staleness(&self, since: Instant) -> bool {
+        let last = self
+            .current
+            .load()
+            .as_ref()
+            .map_or(since, |latest| latest.fetched_at.max(since));
+        let age = last.elapsed();
+        if age <= self.stale_after {
+            return false;
+        }
+        if !self.stale.swap(true, Ordering::Relaxed) {
+            log::warn!("Cached blockhash is stale ({:?} old), falling back to RPC", age);
+        }
+        true
+    }
+
+    /// Resolve once the cache goes stale
+    async fn watch_staleness(&self, since: Instant) {
+        let check_interval = (self.stale_after / 4).max(Duration::from_millis(50));
+        loop {
+            sleep(check_interval).await;
+            if self.check_staleness(since) {
+                return;
+            }
+        }
+    }
+
+    /// Keep the cache fresh; runs forever
+    pub async fn run_refresh(
+        self: Arc<Self>,
+        rpc_client: Arc<RpcClient>,
+        grpc_url: Option<String>,
+        poll_interval: Duration,
+    ) {
+        let mut delay = RECONNECT_DELAY;
+        loop {
+            let mut reconnect_at = None;
+            if let Some(ref grpc_url) = grpc_url {
+                let mut subscribed = false;
+                let result = tokio::select! {
+                    result = self.stream_geyser(grpc_url, &mut subscribed) => result,
+                    _ = self.watch_staleness(Instant::now()) => {
+                        Err(anyhow::anyhow!("no block metadata for {:?}", self.stale_after))
+                    }
+                };
+                if subscribed {
+                    delay = RECONNECT_DELAY;
+                }
+                match result {
+                    Ok(()) => log::warn!(
+                        "Geyser block-meta stream ended, polling for blockhashes and reconnecting in {}s",
+                        delay.as_secs()
+                    ),
+                    Err(e) => log::warn!(
+                        "Geyser block-meta stream failed ({}), polling for blockhashes and reconnecting in {}s",
+                        e,
+                        delay.as_secs()
+                    ),
+                }
+                reconnect_at = Some(Instant::now() + delay);
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+
+            // Without Geyser this polls forever; otherwise it bridges the gap until the reconnect
+            let since = Instant::now();
+            loop {
+                match rpc_client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await
+                {
+                    Ok((blockhash, last_valid_block_height)) => {
+                        self.store(blockhash, last_valid_block_height)
+                    }
+                    Err(e) => log::warn!("Failed to refresh blockhash: {}", e),
+                }
+                self.check_staleness(since);
+
+                if reconnect_at.is_some_and(|at| Instant::now() >= at) {
+                    break;
+                }
+                sleep(poll_interval).await;
+            }
+        }
+    }
+
+    /// Update from confirmed block metadata until the stream ends
+    ///
+    /// Sets `subscribed` once the subscription is established.
+    async fn stream_geyser(&self, grpc_url: &str, subscribed: &mut bool) -> Result<()> {
+        use yellowstone_grpc::{
+            geyser::SubscribeRequest,
+            proto::geyser::{CommitmentLevel, SubscribeRequestFilterBlocksMeta},
+        };
+
+        let mut client = yellowstone_grpc::GeyserGrpcClient::connect(grpc_url)
+            .await
+            .context("Failed to connect to Yellowstone Geyser")?;
+
+        let request = SubscribeRequest {
+            slots: vec![],
+            accounts: vec![],
+            transactions: vec![],
+            transactions_status: vec![],
+            blocks: vec![],
+            blocks_meta: vec![SubscribeRequestFilterBlocksMeta {}],
+            accounts_data_slice: vec![],
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+        };
+
+        let mut stream = client
+            .subscribe_once(request)
+            .await
+            .context("Failed to subscribe to Geyser stream")?;
+        *subscribed = true;
+        log::info!("Tracking blockhashes from Geyser block metadata");
+
+        while let Some(update) = stream.message().await.transpose() {
+            let update = update.context("Error receiving Geyser update")?;
+            let Some(meta) = update.block_meta else {
+                continue;
+            };
+            let Some(block_height) = meta.block_height else {
+                continue;
+            };
+            match Hash::from_str(&meta.blockhash) {
+                Ok(blockhash) => self.store(
+                    blockhash,
+                    block_height.block_height + MAX_PROCESSING_AGE,
+                ),
+                Err(e) => log::debug!("Invalid blockhash in block meta: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::Result;
+use futures::future::join_all;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Signature, transaction::VersionedTransaction,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// Consecutive send failures after which an endpoint is benched
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// How long a benched endpoint is skipped
+const BENCH_DURATION: Duration = Duration::from_secs(30);
+
+/// Signatures remembered per endpoint to tell re-broadcasts from first sends
+const RECENT_SIGNATURES: usize = 64;
+
+/// Send statistics for one endpoint
+///
+/// `sends` and `errors` count each transaction once, on its first send; re-broadcasts
+/// of the same signature are counted separately and never bench the endpoint, since
+/// their errors are mostly the transaction having landed already.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointStats {
+    pub sends: u64,
+    pub errors: u64,
+    pub resends: u64,
+    pub resend_errors: u64,
+    /// Broadcasts that landed after this endpoint accepted them
+    pub landed: u64,
+    /// Landed broadcasts where this endpoint was the first to accept
+    pub landed_first: u64,
+    pub total_latency: Duration,
+    consecutive_failures: u32,
+    benched_until: Option<Instant>,
+    recent: VecDeque<Signature>,
+}
+
+impl EndpointStats {
+    fn is_healthy(&self) -> bool {
+        self.benched_until.map_or(true, |until| Instant::now() >= until)
+    }
+
+    pub fn avg_latency(&self) -> Duration {
+        let ok = self.sends - self.errors;
+        if ok == 0 {
+            Duration::ZERO
+        } else {
+            self.total_latency / ok as u32
+        }
+    }
+
+    /// Fraction of accepted sends that landed
+    pub fn landing_rate(&self) -> f64 {
+        let ok = self.sends - self.errors;
+        if ok == 0 {
+            0.0
+        } else {
+            self.landed as f64 / ok as f64
+        }
+    }
+}
+
+/// A transaction send endpoint (RPC node, TPU-forwarding service, Jito, ...)
+pub struct Endpoint {
+    pub name: String,
+    client: RpcClient,
+    skip_preflight: bool,
+    stats: Mutex<EndpointStats>,
+}
+
+impl Endpoint {
+    pub fn new(name: &str, url: &str, skip_preflight: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            client: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            skip_preflight,
+            stats: Mutex::new(EndpointStats::default()),
+        }
+    }
+
+    async fn send(&self, transaction: &VersionedTransaction) -> Result<Duration> {
+        let start = Instant::now();
+        let result = self
+            .client
+            .send_transaction_with_config(
+                transaction,
+                RpcSendTransactionConfig {
+                    skip_preflight: self.skip_preflight,
+                    max_retries: Some(0),
+                    ..Default::default()
+                },
+            )
+            .await;
+        let latency = start.elapsed();
+
+        let mut stats = self.stats.lock().unwrap();
+        let signature = transaction.signatures[0];
+        if stats.recent.contains(&signature) {
+            stats.resends += 1;
+            if result.is_err() {
+                stats.resend_errors += 1;
+            }
+            return result.map(|_| latency).map_err(Into::into);
+        }
+        stats.recent.push_back(signature);
+        if stats.recent.len() > RECENT_SIGNATURES {
+            stats.recent.pop_front();
+        }
+
+        stats.sends += 1;
+        match result {
+            Ok(_) => {
+                stats.total_latency += latency;
+                stats.consecutive_failures = 0;
+                Ok(latency)
+            }
+            Err(e) => {
+                stats.errors += 1;
+                stats.consecutive_failures += 1;
+                if stats.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                    log::warn!(
+                        "Send endpoint {} failed {} times in a row, benching for {:?}",
+                        self.name,
+                        stats.consecutive_failures,
+                        BENCH_DURATION
+                    );
+                    stats.benched_until = Some(Instant::now() + BENCH_DURATION);
+                }
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Outcome of broadcasting one transaction
+#[derive(Debug, Clone)]
+pub struct BroadcastResult {
+    pub signature: String,
+    /// Endpoints that accepted the transaction, fastest first
+    pub accepted: Vec<(String, Duration)>,
+}
+
+impl BroadcastResult {
+    /// Endpoint that accepted the transaction first
+    ///
+    /// Every copy has the same signature, so the landed transaction can't be
+    /// traced to an endpoint; the first to accept it is credited instead.
+    pub fn first(&self) -> Option<&str> {
+        self.accepted.first().map(|(name, _)| name.as_str())
+    }
+}
+
+/// Fans a signed transaction out to every healthy send endpoint concurrently
+pub struct Broadcaster {
+    endpoints: Vec<Endpoint>,
+}
+
+impl Broadcaster {
+    /// The primary RPC is always included as endpoint `rpc`
+    pub fn from_config(config: &Config) -> Self {
+        let skips = |name: &str| {
+            config
+                .skip_preflight_endpoints
+                .iter()
+                .any(|n| n == "*" || n == name)
+        };
+
+        let mut endpoints = vec![Endpoint::new("rpc", &config.rpc_url, skips("rpc"))];
+        for (name, url) in &config.send_endpoints {
+            endpoints.push(Endpoint::new(name, url, skips(name)));
+        }
+
+        Self { endpoints }
+    }
+
+    /// Send to every healthy endpoint; fails only if none accept
+    pub async fn broadcast(&self, transaction: &VersionedTransaction) -> Result<BroadcastResult> {
+        let signature = transaction.signatures[0].to_string();
+
+        let mut targets: Vec<&Endpoint> = self
+            .endpoints
+            .iter()
+            .filter(|e| e.stats.lock().unwrap().is_healthy())
+            .collect();
+        if targets.is_empty() {
+            // Everything is benched; try them all rather than not sending
+            targets = self.endpoints.iter().collect();
+        }
+
+        let results = join_all(targets.iter().map(|e| e.send(transaction))).await;
+
+        let mut accepted = Vec::new();
+        let mut last_error = None;
+        for (endpoint, result) in targets.iter().zip(results) {
+            match result {
+                Ok(latency) => accepted.push((endpoint.name.clone(), latency)),
+                Err(e) => {
+                    log::debug!("Send via {} failed: {}", endpoint.name, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if accepted.is_empty() {
+            return Err(last_error
+                .unwrap_or_else(|| anyhow::anyhow!("No send endpoints configured")));
+        }
+        accepted.sort_by_key(|(_, latency)| *latency);
+
+        Ok(BroadcastResult {
+            signature,
+            accepted,
+        })
+    }
+
+    /// Credit the endpoints of a broadcast that landed
+    pub fn record_landed(&self, result: &BroadcastResult) {
+        for endpoint in &self.endpoints {
+            if !result.accepted.iter().any(|(name, _)| *name == endpoint.name) {
+                continue;
+            }
+            let mut stats = endpoint.stats.lock().unwrap();
+            stats.landed += 1;
+            if result.first() == Some(endpoint.name.as_str()) {
+                stats.landed_first += 1;
+            }
+        }
+    }
+
+    /// Snapshot of per-endpoint stats
+    pub fn stats(&self) -> Vec<(String, EndpointStats)> {
+        self.endpoints
+            .iter()
+            .map(|e| (e.name.clone(), e.stats.lock().unwrap().clone()))
+            .collect()
+    }
+}
+
+impl fmt::Display for Broadcaster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, stats)) in self.stats().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "  {:<12} sends={} errors={} resends={} landed={} ({:.0}%) first={} avg={:?}{}",
+                name,
+                stats.sends,
+                stats.errors,
+                stats.resends,
+                stats.landed,
+                stats.landing_rate() * 100.0,
+                stats.landed_first,
+                stats.avg_latency(),
+                if stats.is_healthy() { "" } else { " [benched]" }
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,594 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::time::{interval, Duration, Instant};
+
+use crate::quote::Reserves;
+use crate::tracker::{PoolSnapshot, ReserveTracker};
+
+/// How often settled updates are folded in and closed candles written
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a slot's updates are held before being settled without a later slot
+///
+/// Both vaults of a swap are written in the same slot but arrive as separate updates.
+const SETTLE_DELAY: Duration = Duration::from_millis(800);
+
+/// Candle width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    Second,
+    Minute,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 2] = [Resolution::Second, Resolution::Minute];
+
+    pub fn secs(self) -> i64 {
+        match self {
+            Resolution::Second => 1,
+            Resolution::Minute => 60,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Resolution::Second => "1s",
+            Resolution::Minute => "1m",
+        }
+    }
+
+    /// Start of the candle containing a unix timestamp
+    fn bucket(self, timestamp: i64) -> i64 {
+        timestamp - timestamp.rem_euclid(self.secs())
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "1s" | "s" | "second" => Ok(Resolution::Second),
+            "1m" | "m" | "minute" => Ok(Resolution::Minute),
+            _ => anyhow::bail!("Unknown candle resolution '{}' (expected 1s or 1m)", s),
+        }
+    }
+}
+
+/// Price and net flow of a pool over one candle
+///
+/// Prices are SOL per whole token. Flow is derived from the pool's settled
+/// reserves per slot, not from individual swaps: several swaps landing in the
+/// same slot count as their net, and opposing ones cancel out, so it is a lower
+/// bound on traded volume.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Candle {
+    /// Unix timestamp the candle starts at
+    pub start: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Sum of the per-slot net SOL moved through the pool, in lamports
+    #[serde(alias = "volume_lamports")]
+    pub net_flow_lamports: u64,
+    /// Sum of the per-slot net tokens moved through the pool, in raw units
+    #[serde(alias = "volume_tokens")]
+    pub net_flow_tokens: u64,
+    /// Slots in which SOL flowed into the pool on net
+    #[serde(alias = "buys")]
+    pub inflow_slots: u32,
+    /// Slots in which SOL flowed out of the pool on net
+    #[serde(alias = "sells")]
+    pub outflow_slots: u32,
+}
+
+impl Candle {
+    fn new(start: i64, open: f64) -> Self {
+        Self {
+            start,
+            open,
+            high: open,
+            low: open,
+            close: open,
+            net_flow_lamports: 0,
+            net_flow_tokens: 0,
+            inflow_slots: 0,
+            outflow_slots: 0,
+        }
+    }
+
+    fn record(&mut self, price: f64, flow: Option<Flow>) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+
+        if let Some(flow) = flow {
+            self.net_flow_lamports += flow.lamports;
+            self.net_flow_tokens += flow.tokens;
+            if flow.inflow {
+                self.inflow_slots += 1;
+            } else {
+                self.outflow_slots += 1;
+            }
+        }
+    }
+}
+
+/// Net trade flow between two settled reserve states
+#[derive(Debug, Clone, Copy)]
+struct Flow {
+    lamports: u64,
+    tokens: u64,
+    /// SOL went in and tokens came out
+    inflow: bool,
+}
+
+impl Flow {
+    /// Reserves moving in opposite directions are a swap; moving together is a
+    /// liquidity change, which is not flow
+    fn between(before: Reserves, after: Reserves) -> Option<Self> {
+        if after.sol == before.sol || after.token == before.token {
+            return None;
+        }
+        let inflow = after.sol > before.sol;
+        if inflow == (after.token > before.token) {
+            return None;
+        }
+
+        Some(Self {
+            lamports: after.sol.abs_diff(before.sol),
+            tokens: after.token.abs_diff(before.token),
+            inflow,
+        })
+    }
+}
+
+/// Closed candles as one JSON-lines file per mint and resolution
+///
+/// Plain files rather than the embedded database, so they can be queried while the bot runs.
+pub struct CandleStore {
+    dir: PathBuf,
+    /// Files appended to so far, kept open until their pool is closed
+    files: HashMap<(Pubkey, Resolution), File>,
+}
+
+impl CandleStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Move the store onto a blocking task and return a handle queueing writes to it
+    ///
+    /// Must be called from within the runtime.
+    pub fn spawn_writer(mut self) -> CandleSink {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            while let Some(op) = rx.blocking_recv() {
+                match op {
+                    SinkOp::Append(mint, resolution, candle) => {
+                        if let Err(e) = self.append(&mint, resolution, &candle) {
+                            log::warn!(
+                                "Failed to store {} candle of {}: {}",
+                                resolution.label(),
+                                mint,
+                                e
+                            );
+                        }
+                    }
+                    SinkOp::Close(mint) => self.files.retain(|(file_mint, _), _| *file_mint != mint),
+                }
+            }
+        });
+        CandleSink { tx }
+    }
+
+    fn path(&self, mint: &Pubkey, resolution: Resolution) -> PathBuf {
+        self.dir
+            .join(format!("{}-{}.jsonl", mint, resolution.label()))
+    }
+
+    /// Append a closed candle, opening the file on first use
+    pub fn append(&mut self, mint: &Pubkey, resolution: Resolution, candle: &Candle) -> Result<()> {
+        let line = serde_json::to_string(candle)?;
+
+        let key = (*mint, resolution);
+        if !self.files.contains_key(&key) {
+            fs::create_dir_all(&self.dir).with_context(|| {
+                format!("Failed to create candle directory {}", self.dir.display())
+            })?;
+            let path = self.path(mint, resolution);
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            self.files.insert(key, file);
+        }
+
+        let Some(file) = self.files.get_mut(&key) else {
+            return Ok(());
+        };
+        if let Err(e) = writeln!(file, "{}", line) {
+            // Reopen on the next candle in case the file was moved or deleted
+            self.files.remove(&key);
+            return Err(e).context("Failed to write candle");
+        }
+
+        Ok(())
+    }
+
+    /// The most recent candles of a mint, oldest first
+    pub fn load(&self, mint: &Pubkey, resolution: Resolution, limit: usize) -> Result<Vec<Candle>> {
+        let path = self.path(mint, resolution);
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+        };
+
+        let mut candles = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            candles.push(
+                serde_json::from_str(&line)
+                    .with_context(|| format!("Invalid candle in {}", path.display()))?,
+            );
+        }
+
+        let skip = candles.len().saturating_sub(limit);
+        Ok(candles.split_off(skip))
+    }
+}
+
+/// Write queued for the candle writer
+enum SinkOp {
+    Append(Pubkey, Resolution, Candle),
+    /// The pool's series finished; close its files
+    Close(Pubkey),
+}
+
+/// Queues closed candles for the store's blocking writer, so file IO stays off the runtime
+#[derive(Clone)]
+pub struct CandleSink {
+    tx: mpsc::UnboundedSender<SinkOp>,
+}
+
+impl CandleSink {
+    fn write(&self, mint: &Pubkey, resolution: Resolution, candle: &Candle) {
+        if self.tx.send(SinkOp::Append(*mint, resolution, *candle)).is_err() {
+            log::warn!("Candle writer stopped, dropping {} candle of {}", resolution.label(), mint);
+        }
+    }
+
+    fn close(&self, mint: &Pubkey) {
+        let _ = self.tx.send(SinkOp::Close(*mint));
+    }
+}
+
+/// Updates of the latest slot, not yet folded into candles
+struct Pending {
+    snapshot: PoolSnapshot,
+    timestamp: i64,
+    received: Instant,
+}
+
+/// Candles being built for one tracked pool
+struct PoolSeries {
+    mint: Pubkey,
+    /// Reserves at the end of the last settled slot
+    settled: Reserves,
+    last_price: Option<f64>,
+    pending: Option<Pending>,
+    /// Open candle per resolution, in `Resolution::ALL` order
+    candles: [Option<Candle>; 2],
+}
+
+impl PoolSeries {
+    fn new(mint: Pubkey, reserves: Reserves) -> Self {
+        Self {
+            mint,
+            settled: reserves,
+            last_price: None,
+            pending: None,
+            candles: [None; 2],
+        }
+    }
+
+    fn observe(&mut self, snapshot: PoolSnapshot, sink: &CandleSink) {
+        if let Some(pending) = self.pending.take() {
+            if snapshot.slot > pending.snapshot.slot {
+                self.settle(pending, sink);
+            }
+        }
+
+        self.pending = Some(Pending {
+            snapshot,
+            timestamp: chrono::Utc::now().timestamp(),
+            received: Instant::now(),
+        });
+    }
+
+    /// Fold a finished slot into the open candles, writing any it closes
+    fn settle(&mut self, pending: Pending, sink: &CandleSink) {
+        let flow = Flow::between(self.settled, pending.snapshot.reserves);
+        self.settled = pending.snapshot.reserves;
+
+        let price = pending.snapshot.price;
+        if !price.is_finite() || price <= 0.0 {
+            return;
+        }
+
+        for (resolution, current) in Resolution::ALL.iter().zip(self.candles.iter_mut()) {
+            let start = resolution.bucket(pending.timestamp);
+            if let Some(closed) = current.filter(|candle| candle.start != start) {
+                sink.write(&self.mint, *resolution, &closed);
+                *current = None;
+            }
+            current
+                .get_or_insert_with(|| Candle::new(start, self.last_price.unwrap_or(price)))
+                .record(price, flow);
+        }
+        self.last_price = Some(price);
+    }
+
+    /// Settle a quiet slot and write candles whose time has passed
+    fn flush(&mut self, now: i64, sink: &CandleSink) {
+        if let Some(pending) = self.pending.take() {
+            if pending.received.elapsed() >= SETTLE_DELAY {
+                self.settle(pending, sink);
+            } else {
+                self.pending = Some(pending);
+            }
+        }
+
+        for (resolution, current) in Resolution::ALL.iter().zip(self.candles.iter_mut()) {
+            if let Some(closed) = current.filter(|candle| candle.start + resolution.secs() <= now) {
+                sink.write(&self.mint, *resolution, &closed);
+                *current = None;
+            }
+        }
+    }
+
+    /// Write everything still open, once the pool is no longer tracked
+    fn finish(mut self, sink: &CandleSink) {
+        if let Some(pending) = self.pending.take() {
+            self.settle(pending, sink);
+        }
+        for (resolution, candle) in Resolution::ALL.iter().zip(self.candles.iter()) {
+            if let Some(candle) = candle {
+                sink.write(&self.mint, *resolution, candle);
+            }
+        }
+        sink.close(&self.mint);
+    }
+}
+
+/// Turns reserve tracker updates into candles for every tracked pool
+pub struct CandleRecorder {
+    tracker: Arc<ReserveTracker>,
+    sink: CandleSink,
+    series: HashMap<Pubkey, PoolSeries>,
+}
+
+impl CandleRecorder {
+    pub fn new(tracker: Arc<ReserveTracker>, sink: CandleSink) -> Self {
+        Self {
+            tracker,
+            sink,
+            series: HashMap::new(),
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut updates = self.tracker.updates();
+        let mut ticker = interval(FLUSH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Ok((pool, snapshot)) => self.observe(pool, snapshot),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Candle recorder fell behind, skipped {} reserve updates", skipped);
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                _ = ticker.tick() => self.flush(),
+            }
+        }
+    }
+
+    fn observe(&mut self, pool: Pubkey, snapshot: PoolSnapshot) {
+        if !self.series.contains_key(&pool) {
+            let Some(keys) = self.tracker.keys(&pool) else {
+                return;
+            };
+            self.series
+                .insert(pool, PoolSeries::new(keys.token_mint(), snapshot.reserves));
+        }
+        if let Some(series) = self.series.get_mut(&pool) {
+            series.observe(snapshot, &self.sink);
+        }
+    }
+
+    fn flush(&mut self) {
+        let now = chrono::Utc::now().timestamp();
+        let untracked: Vec<Pubkey> = self
+            .series
+            .keys()
+            .filter(|pool| self.tracker.keys(pool).is_none())
+            .copied()
+            .collect();
+        for pool in untracked {
+            if let Some(series) = self.series.remove(&pool) {
+                series.finish(&self.sink);
+            }
+        }
+
+        for series in self.series.values_mut() {
+            series.flush(now, &self.sink);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::program_option::COption;
+
+    fn reserves(sol: u64, token: u64) -> Reserves {
+        Reserves { sol, token }
+    }
+
+    fn pending(slot: u64, reserves: Reserves, price: f64, timestamp: i64) -> Pending {
+        Pending {
+            snapshot: PoolSnapshot {
+                slot,
+                reserves,
+                price,
+                mint_authority: COption::None,
+                freeze_authority: COption::None,
+                lp_supply: 0,
+                updated_at: Instant::now(),
+            },
+            timestamp,
+            received: Instant::now(),
+        }
+    }
+
+    fn sink() -> (CandleSink, mpsc::UnboundedReceiver<SinkOp>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (CandleSink { tx }, rx)
+    }
+
+    /// Candles queued for writing so far, as (resolution, start, open, close)
+    fn written(rx: &mut mpsc::UnboundedReceiver<SinkOp>) -> Vec<(Resolution, i64, f64, f64)> {
+        let mut candles = Vec::new();
+        while let Ok(op) = rx.try_recv() {
+            if let SinkOp::Append(_, resolution, candle) = op {
+                candles.push((resolution, candle.start, candle.open, candle.close));
+            }
+        }
+        candles
+    }
+
+    #[test]
+    fn sol_in_tokens_out_is_inflow() {
+        let flow = Flow::between(reserves(1_000, 1_000), reserves(1_100, 910)).unwrap();
+        assert!(flow.inflow);
+        assert_eq!(flow.lamports, 100);
+        assert_eq!(flow.tokens, 90);
+    }
+
+    #[test]
+    fn tokens_in_sol_out_is_outflow() {
+        let flow = Flow::between(reserves(1_000, 1_000), reserves(900, 1_110)).unwrap();
+        assert!(!flow.inflow);
+        assert_eq!(flow.lamports, 100);
+        assert_eq!(flow.tokens, 110);
+    }
+
+    #[test]
+    fn liquidity_changes_are_not_flow() {
+        assert!(Flow::between(reserves(1_000, 1_000), reserves(1_100, 1_100)).is_none());
+        assert!(Flow::between(reserves(1_000, 1_000), reserves(900, 900)).is_none());
+        assert!(Flow::between(reserves(1_000, 1_000), reserves(1_000, 900)).is_none());
+    }
+
+    #[test]
+    fn buckets_start_on_the_resolution() {
+        assert_eq!(Resolution::Second.bucket(125), 125);
+        assert_eq!(Resolution::Minute.bucket(125), 120);
+        assert_eq!(Resolution::Minute.bucket(120), 120);
+        assert_eq!(Resolution::Minute.bucket(-1), -60);
+    }
+
+    #[test]
+    fn new_candle_opens_at_the_previous_close() {
+        let (sink, mut rx) = sink();
+        let mut series = PoolSeries::new(Pubkey::new_unique(), reserves(1_000, 1_000));
+
+        series.settle(pending(1, reserves(1_100, 910), 1.0, 60), &sink);
+        series.settle(pending(2, reserves(1_200, 830), 2.0, 61), &sink);
+
+        assert_eq!(written(&mut rx), vec![(Resolution::Second, 60, 1.0, 1.0)]);
+
+        let second = series.candles[0].unwrap();
+        assert_eq!(
+            (second.start, second.open, second.close, second.high),
+            (61, 1.0, 2.0, 2.0)
+        );
+        assert_eq!(second.inflow_slots, 1);
+        assert_eq!(second.net_flow_lamports, 100);
+
+        let minute = series.candles[1].unwrap();
+        assert_eq!((minute.start, minute.open, minute.close), (60, 1.0, 2.0));
+        assert_eq!(minute.inflow_slots, 2);
+        assert_eq!(minute.net_flow_lamports, 200);
+    }
+
+    #[test]
+    fn flush_settles_quiet_slots_and_closes_elapsed_candles() {
+        let (sink, mut rx) = sink();
+        let mut series = PoolSeries::new(Pubkey::new_unique(), reserves(1_000, 1_000));
+        series.settle(pending(1, reserves(1_100, 910), 1.0, 60), &sink);
+
+        let mut quiet = pending(2, reserves(1_000, 1_000), 0.5, 61);
+        quiet.received = Instant::now() - SETTLE_DELAY;
+        series.pending = Some(quiet);
+        series.flush(62, &sink);
+
+        assert!(series.pending.is_none());
+        assert_eq!(
+            written(&mut rx),
+            vec![
+                (Resolution::Second, 60, 1.0, 1.0),
+                (Resolution::Second, 61, 1.0, 0.5),
+            ]
+        );
+        assert!(series.candles[0].is_none());
+        assert_eq!(series.candles[1].unwrap().close, 0.5);
+
+        // A slot that may still get updates is held back
+        series.pending = Some(pending(3, reserves(900, 1_110), 0.4, 62));
+        series.flush(120, &sink);
+        assert!(series.pending.is_some());
+        assert_eq!(written(&mut rx), vec![(Resolution::Minute, 60, 1.0, 0.5)]);
+    }
+
+    #[test]
+    fn load_keeps_the_latest_candles() {
+        let dir = std::env::temp_dir().join(format!("candles-test-{}", std::process::id()));
+        let mut store = CandleStore::new(&dir);
+        let mint = Pubkey::new_unique();
+        for start in 0..5 {
+            store
+                .append(&mint, Resolution::Second, &Candle::new(start, start as f64))
+                .unwrap();
+        }
+
+        let starts = |candles: Vec<Candle>| candles.iter().map(|c| c.start).collect::<Vec<_>>();
+        let loaded = store.load(&mint, Resolution::Second, 3).unwrap();
+        assert_eq!(starts(loaded), vec![2, 3, 4]);
+        let loaded = store.load(&mint, Resolution::Second, 10).unwrap();
+        assert_eq!(starts(loaded), vec![0, 1, 2, 3, 4]);
+        assert!(store.load(&mint, Resolution::Minute, 3).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+use crate::detector::PoolType;
+use crate::utils::{self, Persisted};
+
+/// Number of recent samples kept per pool type
+const MAX_SAMPLES: usize = 50;
+
+/// Minimum samples before the learned profile is trusted over the configured limit
+const MIN_SAMPLES: usize = 3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfileData {
+    /// Recent consumed-CU samples per pool type
+    samples: HashMap<PoolType, VecDeque<u32>>,
+    /// Priority fees saved versus always requesting `max_compute_units`
+    total_saved_lamports: u64,
+}
+
+/// Compute-unit usage learned from simulated and landed swaps, per pool type
+///
+/// Samples are kept in memory and written by `utils::run_persist`, off the buy path.
+pub struct ComputeProfile {
+    path: PathBuf,
+    max_compute_units: u32,
+    margin_bps: u16,
+    data: Mutex<ProfileData>,
+    /// Changed since the last write
+    dirty: AtomicBool,
+}
+
+impl ComputeProfile {
+    /// Load the profile from disk, starting empty if the file doesn't exist
+    pub fn load(path: impl Into<PathBuf>, max_compute_units: u32, margin_bps: u16) -> Result<Self> {
+        let path = path.into();
+        let data = if path.exists() {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read compute profile {}", path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("Failed to parse compute profile {}", path.display()))?
+        } else {
+            ProfileData::default()
+        };
+
+        Ok(Self {
+            path,
+            max_compute_units,
+            margin_bps,
+            data: Mutex::new(data),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// Add the safety margin to a consumed-CU figure, capped at the configured maximum
+    pub fn with_margin(&self, units: u32) -> u32 {
+        let padded = units as u64 * (10_000 + self.margin_bps as u64) / 10_000;
+        (padded as u32).min(self.max_compute_units)
+    }
+
+    /// Record CUs consumed by a simulated or landed swap
+    pub fn record(&self, pool_type: PoolType, units: u32) {
+        let mut data = self.data.lock().unwrap();
+        let samples = data.samples.entry(pool_type).or_default();
+        samples.push_back(units);
+        while samples.len() > MAX_SAMPLES {
+            samples.pop_front();
+        }
+        self.mark_dirty();
+    }
+
+    /// CU limit to request for a pool type without simulating
+    ///
+    /// Uses the highest recent sample plus margin, or `max_compute_units`
+    /// until enough samples have been collected.
+    pub fn limit_for(&self, pool_type: PoolType) -> u32 {
+        let data = self.data.lock().unwrap();
+        match data.samples.get(&pool_type) {
+            Some(samples) if samples.len() >= MIN_SAMPLES => {
+                self.with_margin(samples.iter().copied().max().unwrap_or(self.max_compute_units))
+            }
+            _ => self.max_compute_units,
+        }
+    }
+
+    /// Lamports saved by requesting `limit` instead of `max_compute_units`
+    pub fn savings(&self, limit: u32, micro_lamports_per_cu: u64) -> u64 {
+        let saved_units = self.max_compute_units.saturating_sub(limit) as u64;
+        saved_units * micro_lamports_per_cu / 1_000_000
+    }
+
+    /// Add the savings of a landed transaction to the total
+    ///
+    /// Only landed transactions pay a priority fee, so only they save anything.
+    pub fn record_savings(&self, saved_lamports: u64) {
+        self.data.lock().unwrap().total_saved_lamports += saved_lamports;
+        self.mark_dirty();
+    }
+
+    /// Total priority fees saved so far
+    pub fn total_saved_lamports(&self) -> u64 {
+        self.data.lock().unwrap().total_saved_lamports
+    }
+}
+
+impl Persisted for ComputeProfile {
+    const NAME: &'static str = "compute profile";
+
+    fn dirty(&self) -> &AtomicBool {
+        &self.dirty
+    }
+
+    fn save(&self) -> Result<()> {
+        let text = serde_json::to_string_pretty(&*self.data.lock().unwrap())?;
+        utils::write_atomic(&self.path, text.as_bytes())
+    }
+}
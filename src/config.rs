@@ -1,4 +1,7 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::HashMap;
 use std::str::FromStr;
 
 /// Raydium Legacy AMM v4 Program ID
@@ -20,8 +23,16 @@ pub struct Config {
     pub mnemonic: Option<String>,
     /// Buy amount in SOL
     pub buy_amount_sol: f64,
-    /// Priority fee in micro-lamports
+    /// Priority fee in micro-lamports (fallback when no recent fees are available)
     pub priority_fee_micro_lamports: u64,
+    /// Percentile of recent prioritization fees to pay (0 - 100)
+    pub priority_fee_percentile: u8,
+    /// Lower bound for the dynamic priority fee
+    pub priority_fee_min_micro_lamports: u64,
+    /// Upper bound for the dynamic priority fee
+    pub priority_fee_max_micro_lamports: u64,
+    /// Number of slots a fee estimate stays cached
+    pub priority_fee_cache_slots: u64,
     /// Minimum initial liquidity in USD
     pub min_liquidity_usd: f64,
     /// Maximum initial liquidity in USD (None = no limit)
@@ -32,14 +43,52 @@ pub struct Config {
     pub dry_run: bool,
     /// Enable Jito bundle support
     pub jito_enabled: bool,
-    /// Jito tip amount in lamports
+    /// Jito tip amount in lamports (used until tip floor data is available)
     pub jito_tip_lamports: u64,
+    /// Tip floor endpoint (None = Jito's public endpoint)
+    pub jito_tip_floor_url: Option<String>,
+    /// Landed-tip percentile to bid (25, 50, 75, 95 or 99)
+    pub jito_tip_percentile: u8,
+    /// Tip multiplier applied at a perfect score (scales linearly from 1.0 at score 0)
+    pub jito_tip_score_multiplier: f64,
+    /// Lower bound for a single tip
+    pub jito_tip_min_lamports: u64,
+    /// Upper bound for a single tip
+    pub jito_tip_max_lamports: u64,
+    /// Maximum total tips per rolling hour (None = unlimited)
+    pub jito_tips_per_hour_cap_lamports: Option<u64>,
     /// Jito block engine URL
     pub jito_block_engine_url: Option<String>,
+    /// How long to wait for a bundle to land before falling back to RPC (ms)
+    pub jito_bundle_timeout_ms: u64,
     /// Maximum compute units for transactions
     pub max_compute_units: u32,
+    /// Simulate each buy first and set the CU limit to consumed units plus margin
+    pub simulate_compute_units: bool,
+    /// Safety margin added to consumed/learned CUs, in basis points
+    pub compute_unit_margin_bps: u16,
+    /// Path of the learned per-pool-type compute unit profile (JSON)
+    pub compute_profile_path: String,
     /// Slippage tolerance in basis points
     pub slippage_bps: u16,
+    /// How often the background task polls for a new blockhash (ms)
+    pub blockhash_refresh_ms: u64,
+    /// Age after which the cached blockhash is considered stale (ms)
+    pub blockhash_stale_ms: u64,
+    /// Commitment a buy must reach to count as landed
+    pub confirmation_commitment: CommitmentConfig,
+    /// How often an unconfirmed transaction is re-broadcast (ms)
+    pub rebroadcast_interval_ms: u64,
+    /// Solana PubSub WebSocket URL (None = derived from rpc_url)
+    pub websocket_url: Option<String>,
+    /// Send buys directly to leader TPUs over QUIC
+    pub tpu_enabled: bool,
+    /// Number of leaders (current + upcoming) to send to over TPU
+    pub tpu_leader_fanout: u64,
+    /// Additional send endpoints as (name, url); the transaction is fanned out to all of them
+    pub send_endpoints: Vec<(String, String)>,
+    /// Endpoint names that skip preflight simulation ("*" = all, "rpc" = primary RPC)
+    pub skip_preflight_endpoints: Vec<String>,
     /// Use WebSocket fallback if gRPC unavailable
     pub use_websocket_fallback: bool,
     /// Rate limit delay between RPC calls (ms)
@@ -48,6 +97,82 @@ pub struct Config {
     pub monitor_amm_v4: bool,
     /// Monitor Raydium CPMM
     pub monitor_cpmm: bool,
+    /// Reject tokens whose metadata name matches any of these patterns
+    pub metadata_name_deny: Vec<Regex>,
+    /// Require the metadata name to match one of these patterns (empty = allow all)
+    pub metadata_name_allow: Vec<Regex>,
+    /// Reject tokens whose metadata symbol matches any of these patterns
+    pub metadata_symbol_deny: Vec<Regex>,
+    /// Require the metadata symbol to match one of these patterns (empty = allow all)
+    pub metadata_symbol_allow: Vec<Regex>,
+    /// Reject tokens with mutable metadata
+    pub reject_mutable_metadata: bool,
+    /// Reject tokens whose metadata update authority is still the pool creator
+    pub reject_creator_update_authority: bool,
+    /// Path of the creator reputation store (JSON)
+    pub creator_db_path: String,
+    /// Reject creators with at least this many rugged launches
+    pub creator_max_rugs: usize,
+    /// Reject creators whose reputation score is below this (0.0 - 1.0)
+    pub creator_min_reputation: f64,
+    /// How long after launch a pool is watched before it counts as survived (seconds)
+    pub creator_outcome_window_secs: u64,
+    /// Number of SOL transfer hops to trace back from the creator (0 = disabled)
+    pub funding_trace_hops: usize,
+    /// Stop tracing at wallets with at least this many transactions
+    pub funding_trace_max_signatures: usize,
+    /// Blacklist of funding wallets; creators funded by these are rejected
+    pub blacklisted_funders: Vec<String>,
+    /// Let pools through when a filter errors (default: an error rejects)
+    pub filter_errors_pass: bool,
+    /// Enable weighted scoring (otherwise any pool without a hard reject is bought)
+    pub scoring_enabled: bool,
+    /// Minimum weighted score (0.0 - 1.0) required to buy in scoring mode
+    pub score_threshold: f64,
+    /// Per-filter score weights by filter name (unlisted filters weigh 1.0)
+    pub filter_weights: HashMap<String, f64>,
+    /// Buy amount at the score threshold (None = buy_amount_sol)
+    pub buy_amount_min_sol: Option<f64>,
+    /// Buy amount at a perfect score (None = buy_amount_sol)
+    pub buy_amount_max_sol: Option<f64>,
+    /// Path of the JSON-lines trade log
+    pub trade_log_path: String,
+    /// Directory of the embedded position database
+    pub positions_db_path: String,
+    /// Take-profit tiers as (value multiple, fraction of the initial position to sell)
+    pub take_profit_tiers: Vec<(f64, f64)>,
+    /// Sell the whole position once it has lost this percentage of its cost (None = disabled)
+    pub stop_loss_pct: Option<f64>,
+    /// Sell everything once value falls this percentage below its peak (None = disabled)
+    pub trailing_stop_pct: Option<f64>,
+    /// Trailing stop percentage by entry score as (minimum score, percent); overrides the default
+    pub trailing_stop_buckets: Vec<(f64, f64)>,
+    /// Sell everything once a position has been held this long (None = no limit)
+    pub max_hold_secs: Option<u64>,
+    /// Maximum hold time by entry score as (minimum score, seconds); overrides the default
+    pub max_hold_buckets: Vec<(f64, u64)>,
+    /// How often held positions are re-valued against pool reserves (ms)
+    pub exit_check_interval_ms: u64,
+    /// Watch open positions' pools and mints and sell immediately on signs of a rug
+    pub emergency_exit_enabled: bool,
+    /// SOL reserve drop within a single update that triggers an emergency exit (percent)
+    pub emergency_reserve_drop_pct: f64,
+    /// Share of a pool's LP supply withdrawn at once that triggers an emergency exit (percent)
+    pub emergency_withdraw_pct: f64,
+    /// Send emergency sells as Jito bundles (requires Jito to be enabled)
+    pub emergency_use_jito: bool,
+    /// How long a pool's price keeps being recorded once no position holds it (seconds)
+    pub price_history_secs: u64,
+    /// Directory candles of tracked pools are written to
+    pub candles_dir: String,
+    /// Path of a declarative filter rules file (optional)
+    pub rules_path: Option<String>,
+    /// Pre-sign buys for pools that open later against durable nonces
+    pub use_durable_nonce: bool,
+    /// Path of the JSON list of durable nonce accounts
+    pub nonce_accounts_path: String,
+    /// Address lookup table used to compress buy transactions (optional)
+    pub lookup_table_address: Option<String>,
 }
 
 impl Default for Config {
@@ -59,19 +184,80 @@ impl Default for Config {
             mnemonic: None,
             buy_amount_sol: 0.1,
             priority_fee_micro_lamports: 100_000, // 0.0001 SOL
+            priority_fee_percentile: 75,
+            priority_fee_min_micro_lamports: 10_000,
+            priority_fee_max_micro_lamports: 5_000_000,
+            priority_fee_cache_slots: 10,
             min_liquidity_usd: 1000.0,
             max_liquidity_usd: None,
             blacklisted_creators: vec![],
             dry_run: true,
             jito_enabled: false,
             jito_tip_lamports: 10_000,
+            jito_tip_floor_url: None,
+            jito_tip_percentile: 75,
+            jito_tip_score_multiplier: 1.0,
+            jito_tip_min_lamports: 1_000,
+            jito_tip_max_lamports: 5_000_000, // 0.005 SOL
+            jito_tips_per_hour_cap_lamports: None,
             jito_block_engine_url: None,
+            jito_bundle_timeout_ms: 5000,
             max_compute_units: 1_400_000,
+            simulate_compute_units: false,
+            compute_unit_margin_bps: 1000, // 10%
+            compute_profile_path: "compute_profile.json".to_string(),
             slippage_bps: 50,
+            blockhash_refresh_ms: 400,
+            blockhash_stale_ms: 5000,
+            confirmation_commitment: CommitmentConfig::confirmed(),
+            rebroadcast_interval_ms: 400,
+            websocket_url: None,
+            tpu_enabled: false,
+            tpu_leader_fanout: 3,
+            send_endpoints: vec![],
+            skip_preflight_endpoints: vec![],
             use_websocket_fallback: true,
             rate_limit_ms: 100,
             monitor_amm_v4: true,
             monitor_cpmm: true,
+            metadata_name_deny: vec![],
+            metadata_name_allow: vec![],
+            metadata_symbol_deny: vec![],
+            metadata_symbol_allow: vec![],
+            reject_mutable_metadata: false,
+            reject_creator_update_authority: false,
+            creator_db_path: "creators.json".to_string(),
+            creator_max_rugs: 2,
+            creator_min_reputation: 0.3,
+            creator_outcome_window_secs: 3600,
+            funding_trace_hops: 2,
+            funding_trace_max_signatures: 200,
+            blacklisted_funders: vec![],
+            filter_errors_pass: false,
+            scoring_enabled: false,
+            score_threshold: 0.6,
+            filter_weights: HashMap::new(),
+            buy_amount_min_sol: None,
+            buy_amount_max_sol: None,
+            trade_log_path: "trades.jsonl".to_string(),
+            positions_db_path: "positions.db".to_string(),
+            take_profit_tiers: vec![],
+            stop_loss_pct: None,
+            trailing_stop_pct: None,
+            trailing_stop_buckets: vec![],
+            max_hold_secs: None,
+            max_hold_buckets: vec![],
+            exit_check_interval_ms: 2000,
+            emergency_exit_enabled: false,
+            emergency_reserve_drop_pct: 30.0,
+            emergency_withdraw_pct: 10.0,
+            emergency_use_jito: false,
+            price_history_secs: 3600,
+            candles_dir: "candles".to_string(),
+            rules_path: None,
+            use_durable_nonce: false,
+            nonce_accounts_path: "nonce_accounts.json".to_string(),
+            lookup_table_address: None,
         }
     }
 }
@@ -107,6 +293,26 @@ impl Config {
                 .map_err(|e| anyhow::anyhow!("Invalid PRIORITY_FEE_MICRO_LAMPORTS: {}", e))?;
         }
 
+        if let Ok(percentile) = std::env::var("PRIORITY_FEE_PERCENTILE") {
+            config.priority_fee_percentile = u8::from_str(&percentile)
+                .map_err(|e| anyhow::anyhow!("Invalid PRIORITY_FEE_PERCENTILE: {}", e))?;
+        }
+
+        if let Ok(min_fee) = std::env::var("PRIORITY_FEE_MIN_MICRO_LAMPORTS") {
+            config.priority_fee_min_micro_lamports = u64::from_str(&min_fee)
+                .map_err(|e| anyhow::anyhow!("Invalid PRIORITY_FEE_MIN_MICRO_LAMPORTS: {}", e))?;
+        }
+
+        if let Ok(max_fee) = std::env::var("PRIORITY_FEE_MAX_MICRO_LAMPORTS") {
+            config.priority_fee_max_micro_lamports = u64::from_str(&max_fee)
+                .map_err(|e| anyhow::anyhow!("Invalid PRIORITY_FEE_MAX_MICRO_LAMPORTS: {}", e))?;
+        }
+
+        if let Ok(cache_slots) = std::env::var("PRIORITY_FEE_CACHE_SLOTS") {
+            config.priority_fee_cache_slots = u64::from_str(&cache_slots)
+                .map_err(|e| anyhow::anyhow!("Invalid PRIORITY_FEE_CACHE_SLOTS: {}", e))?;
+        }
+
         if let Ok(min_liq) = std::env::var("MIN_LIQUIDITY_USD") {
             config.min_liquidity_usd = f64::from_str(&min_liq)
                 .map_err(|e| anyhow::anyhow!("Invalid MIN_LIQUIDITY_USD: {}", e))?;
@@ -138,20 +344,125 @@ impl Config {
                 .map_err(|e| anyhow::anyhow!("Invalid JITO_TIP_LAMPORTS: {}", e))?;
         }
 
+        if let Ok(url) = std::env::var("JITO_TIP_FLOOR_URL") {
+            config.jito_tip_floor_url = Some(url);
+        }
+
+        if let Ok(percentile) = std::env::var("JITO_TIP_PERCENTILE") {
+            config.jito_tip_percentile = u8::from_str(&percentile)
+                .map_err(|e| anyhow::anyhow!("Invalid JITO_TIP_PERCENTILE: {}", e))?;
+            if config.jito_tip_percentile > 100 {
+                anyhow::bail!("Invalid JITO_TIP_PERCENTILE: {} is above 100", percentile);
+            }
+        }
+
+        if let Ok(multiplier) = std::env::var("JITO_TIP_SCORE_MULTIPLIER") {
+            config.jito_tip_score_multiplier = f64::from_str(&multiplier)
+                .map_err(|e| anyhow::anyhow!("Invalid JITO_TIP_SCORE_MULTIPLIER: {}", e))?;
+        }
+
+        if let Ok(min_tip) = std::env::var("JITO_TIP_MIN_LAMPORTS") {
+            config.jito_tip_min_lamports = u64::from_str(&min_tip)
+                .map_err(|e| anyhow::anyhow!("Invalid JITO_TIP_MIN_LAMPORTS: {}", e))?;
+        }
+
+        if let Ok(max_tip) = std::env::var("JITO_TIP_MAX_LAMPORTS") {
+            config.jito_tip_max_lamports = u64::from_str(&max_tip)
+                .map_err(|e| anyhow::anyhow!("Invalid JITO_TIP_MAX_LAMPORTS: {}", e))?;
+        }
+
+        if let Ok(cap) = std::env::var("JITO_TIPS_PER_HOUR_CAP_LAMPORTS") {
+            config.jito_tips_per_hour_cap_lamports = Some(u64::from_str(&cap)
+                .map_err(|e| anyhow::anyhow!("Invalid JITO_TIPS_PER_HOUR_CAP_LAMPORTS: {}", e))?);
+        }
+
         if let Ok(jito_url) = std::env::var("JITO_BLOCK_ENGINE_URL") {
             config.jito_block_engine_url = Some(jito_url);
         }
 
+        if let Ok(timeout) = std::env::var("JITO_BUNDLE_TIMEOUT_MS") {
+            config.jito_bundle_timeout_ms = u64::from_str(&timeout)
+                .map_err(|e| anyhow::anyhow!("Invalid JITO_BUNDLE_TIMEOUT_MS: {}", e))?;
+        }
+
         if let Ok(compute_units) = std::env::var("MAX_COMPUTE_UNITS") {
             config.max_compute_units = u32::from_str(&compute_units)
                 .map_err(|e| anyhow::anyhow!("Invalid MAX_COMPUTE_UNITS: {}", e))?;
         }
 
+        if let Ok(simulate) = std::env::var("SIMULATE_COMPUTE_UNITS") {
+            config.simulate_compute_units = simulate.to_lowercase() == "true" || simulate == "1";
+        }
+
+        if let Ok(margin) = std::env::var("COMPUTE_UNIT_MARGIN_BPS") {
+            config.compute_unit_margin_bps = u16::from_str(&margin)
+                .map_err(|e| anyhow::anyhow!("Invalid COMPUTE_UNIT_MARGIN_BPS: {}", e))?;
+        }
+
+        if let Ok(path) = std::env::var("COMPUTE_PROFILE_PATH") {
+            config.compute_profile_path = path;
+        }
+
         if let Ok(slippage) = std::env::var("SLIPPAGE_BPS") {
             config.slippage_bps = u16::from_str(&slippage)
                 .map_err(|e| anyhow::anyhow!("Invalid SLIPPAGE_BPS: {}", e))?;
         }
 
+        if let Ok(refresh) = std::env::var("BLOCKHASH_REFRESH_MS") {
+            config.blockhash_refresh_ms = u64::from_str(&refresh)
+                .map_err(|e| anyhow::anyhow!("Invalid BLOCKHASH_REFRESH_MS: {}", e))?;
+        }
+
+        if let Ok(stale) = std::env::var("BLOCKHASH_STALE_MS") {
+            config.blockhash_stale_ms = u64::from_str(&stale)
+                .map_err(|e| anyhow::anyhow!("Invalid BLOCKHASH_STALE_MS: {}", e))?;
+        }
+
+        if let Ok(commitment) = std::env::var("CONFIRMATION_COMMITMENT") {
+            config.confirmation_commitment = CommitmentConfig::from_str(&commitment)
+                .map_err(|e| anyhow::anyhow!("Invalid CONFIRMATION_COMMITMENT: {}", e))?;
+        }
+
+        if let Ok(interval) = std::env::var("REBROADCAST_INTERVAL_MS") {
+            config.rebroadcast_interval_ms = u64::from_str(&interval)
+                .map_err(|e| anyhow::anyhow!("Invalid REBROADCAST_INTERVAL_MS: {}", e))?;
+        }
+
+        if let Ok(ws_url) = std::env::var("WEBSOCKET_URL") {
+            config.websocket_url = Some(ws_url);
+        }
+
+        if let Ok(tpu) = std::env::var("USE_TPU") {
+            config.tpu_enabled = tpu.to_lowercase() == "true" || tpu == "1";
+        }
+
+        if let Ok(fanout) = std::env::var("TPU_LEADER_FANOUT") {
+            config.tpu_leader_fanout = u64::from_str(&fanout)
+                .map_err(|e| anyhow::anyhow!("Invalid TPU_LEADER_FANOUT: {}", e))?;
+        }
+
+        if let Ok(endpoints) = std::env::var("SEND_ENDPOINTS") {
+            config.send_endpoints = endpoints
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|entry| {
+                    entry
+                        .split_once('=')
+                        .map(|(name, url)| (name.trim().to_string(), url.trim().to_string()))
+                        .ok_or_else(|| anyhow::anyhow!("Invalid SEND_ENDPOINTS entry '{}': expected name=url", entry))
+                })
+                .collect::<anyhow::Result<_>>()?;
+        }
+
+        if let Ok(names) = std::env::var("SKIP_PREFLIGHT_ENDPOINTS") {
+            config.skip_preflight_endpoints = names
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
         if let Ok(use_ws) = std::env::var("USE_WEBSOCKET_FALLBACK") {
             config.use_websocket_fallback = use_ws.to_lowercase() == "true" || use_ws == "1";
         }
@@ -169,6 +480,182 @@ impl Config {
             config.monitor_cpmm = monitor_cpmm.to_lowercase() == "true" || monitor_cpmm == "1";
         }
 
+        if let Ok(patterns) = std::env::var("METADATA_NAME_DENY") {
+            config.metadata_name_deny = parse_regex_list("METADATA_NAME_DENY", &patterns)?;
+        }
+
+        if let Ok(patterns) = std::env::var("METADATA_NAME_ALLOW") {
+            config.metadata_name_allow = parse_regex_list("METADATA_NAME_ALLOW", &patterns)?;
+        }
+
+        if let Ok(patterns) = std::env::var("METADATA_SYMBOL_DENY") {
+            config.metadata_symbol_deny = parse_regex_list("METADATA_SYMBOL_DENY", &patterns)?;
+        }
+
+        if let Ok(patterns) = std::env::var("METADATA_SYMBOL_ALLOW") {
+            config.metadata_symbol_allow = parse_regex_list("METADATA_SYMBOL_ALLOW", &patterns)?;
+        }
+
+        if let Ok(reject_mutable) = std::env::var("REJECT_MUTABLE_METADATA") {
+            config.reject_mutable_metadata =
+                reject_mutable.to_lowercase() == "true" || reject_mutable == "1";
+        }
+
+        if let Ok(reject_creator) = std::env::var("REJECT_CREATOR_UPDATE_AUTHORITY") {
+            config.reject_creator_update_authority =
+                reject_creator.to_lowercase() == "true" || reject_creator == "1";
+        }
+
+        if let Ok(path) = std::env::var("CREATOR_DB_PATH") {
+            config.creator_db_path = path;
+        }
+
+        if let Ok(max_rugs) = std::env::var("CREATOR_MAX_RUGS") {
+            config.creator_max_rugs = usize::from_str(&max_rugs)
+                .map_err(|e| anyhow::anyhow!("Invalid CREATOR_MAX_RUGS: {}", e))?;
+        }
+
+        if let Ok(min_rep) = std::env::var("CREATOR_MIN_REPUTATION") {
+            config.creator_min_reputation = f64::from_str(&min_rep)
+                .map_err(|e| anyhow::anyhow!("Invalid CREATOR_MIN_REPUTATION: {}", e))?;
+        }
+
+        if let Ok(window) = std::env::var("CREATOR_OUTCOME_WINDOW_SECS") {
+            config.creator_outcome_window_secs = u64::from_str(&window)
+                .map_err(|e| anyhow::anyhow!("Invalid CREATOR_OUTCOME_WINDOW_SECS: {}", e))?;
+        }
+
+        if let Ok(hops) = std::env::var("FUNDING_TRACE_HOPS") {
+            config.funding_trace_hops = usize::from_str(&hops)
+                .map_err(|e| anyhow::anyhow!("Invalid FUNDING_TRACE_HOPS: {}", e))?;
+        }
+
+        if let Ok(max_sigs) = std::env::var("FUNDING_TRACE_MAX_SIGNATURES") {
+            config.funding_trace_max_signatures = usize::from_str(&max_sigs)
+                .map_err(|e| anyhow::anyhow!("Invalid FUNDING_TRACE_MAX_SIGNATURES: {}", e))?;
+        }
+
+        if let Ok(blacklist) = std::env::var("BLACKLIST_FUNDERS") {
+            config.blacklisted_funders = blacklist
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(pass) = std::env::var("FILTER_ERRORS_PASS") {
+            config.filter_errors_pass = pass.to_lowercase() == "true" || pass == "1";
+        }
+
+        if let Ok(scoring) = std::env::var("SCORING_ENABLED") {
+            config.scoring_enabled = scoring.to_lowercase() == "true" || scoring == "1";
+        }
+
+        if let Ok(threshold) = std::env::var("SCORE_THRESHOLD") {
+            config.score_threshold = f64::from_str(&threshold)
+                .map_err(|e| anyhow::anyhow!("Invalid SCORE_THRESHOLD: {}", e))?;
+        }
+
+        if let Ok(weights) = std::env::var("FILTER_WEIGHTS") {
+            config.filter_weights = parse_weights(&weights)?;
+        }
+
+        if let Ok(min_buy) = std::env::var("BUY_AMOUNT_MIN_SOL") {
+            config.buy_amount_min_sol = Some(f64::from_str(&min_buy)
+                .map_err(|e| anyhow::anyhow!("Invalid BUY_AMOUNT_MIN_SOL: {}", e))?);
+        }
+
+        if let Ok(max_buy) = std::env::var("BUY_AMOUNT_MAX_SOL") {
+            config.buy_amount_max_sol = Some(f64::from_str(&max_buy)
+                .map_err(|e| anyhow::anyhow!("Invalid BUY_AMOUNT_MAX_SOL: {}", e))?);
+        }
+
+        if let Ok(path) = std::env::var("TRADE_LOG_PATH") {
+            config.trade_log_path = path;
+        }
+
+        if let Ok(path) = std::env::var("POSITIONS_DB_PATH") {
+            config.positions_db_path = path;
+        }
+
+        if let Ok(tiers) = std::env::var("TAKE_PROFIT_TIERS") {
+            config.take_profit_tiers = parse_take_profit_tiers(&tiers)?;
+        }
+
+        if let Ok(stop_loss) = std::env::var("STOP_LOSS_PCT") {
+            config.stop_loss_pct = Some(f64::from_str(&stop_loss)
+                .map_err(|e| anyhow::anyhow!("Invalid STOP_LOSS_PCT: {}", e))?);
+        }
+
+        if let Ok(trailing) = std::env::var("TRAILING_STOP_PCT") {
+            config.trailing_stop_pct = Some(f64::from_str(&trailing)
+                .map_err(|e| anyhow::anyhow!("Invalid TRAILING_STOP_PCT: {}", e))?);
+        }
+
+        if let Ok(buckets) = std::env::var("TRAILING_STOP_BUCKETS") {
+            config.trailing_stop_buckets = parse_score_buckets("TRAILING_STOP_BUCKETS", &buckets)?;
+        }
+
+        if let Ok(max_hold) = std::env::var("MAX_HOLD_SECS") {
+            config.max_hold_secs = Some(u64::from_str(&max_hold)
+                .map_err(|e| anyhow::anyhow!("Invalid MAX_HOLD_SECS: {}", e))?);
+        }
+
+        if let Ok(buckets) = std::env::var("MAX_HOLD_BUCKETS") {
+            config.max_hold_buckets = parse_score_buckets("MAX_HOLD_BUCKETS", &buckets)?
+                .into_iter()
+                .map(|(min_score, secs)| (min_score, secs as u64))
+                .collect();
+        }
+
+        if let Ok(interval) = std::env::var("EXIT_CHECK_INTERVAL_MS") {
+            config.exit_check_interval_ms = u64::from_str(&interval)
+                .map_err(|e| anyhow::anyhow!("Invalid EXIT_CHECK_INTERVAL_MS: {}", e))?;
+        }
+
+        if let Ok(emergency) = std::env::var("EMERGENCY_EXIT") {
+            config.emergency_exit_enabled = emergency.to_lowercase() == "true" || emergency == "1";
+        }
+
+        if let Ok(drop) = std::env::var("EMERGENCY_RESERVE_DROP_PCT") {
+            config.emergency_reserve_drop_pct = f64::from_str(&drop)
+                .map_err(|e| anyhow::anyhow!("Invalid EMERGENCY_RESERVE_DROP_PCT: {}", e))?;
+        }
+
+        if let Ok(pct) = std::env::var("EMERGENCY_WITHDRAW_PCT") {
+            config.emergency_withdraw_pct = f64::from_str(&pct)
+                .map_err(|e| anyhow::anyhow!("Invalid EMERGENCY_WITHDRAW_PCT: {}", e))?;
+        }
+
+        if let Ok(use_jito) = std::env::var("EMERGENCY_USE_JITO") {
+            config.emergency_use_jito = use_jito.to_lowercase() == "true" || use_jito == "1";
+        }
+
+        if let Ok(secs) = std::env::var("PRICE_HISTORY_SECS") {
+            config.price_history_secs = u64::from_str(&secs)
+                .map_err(|e| anyhow::anyhow!("Invalid PRICE_HISTORY_SECS: {}", e))?;
+        }
+
+        if let Ok(dir) = std::env::var("CANDLES_DIR") {
+            config.candles_dir = dir;
+        }
+
+        if let Ok(path) = std::env::var("RULES_PATH") {
+            config.rules_path = Some(path);
+        }
+
+        if let Ok(nonce) = std::env::var("USE_DURABLE_NONCE") {
+            config.use_durable_nonce = nonce.to_lowercase() == "true" || nonce == "1";
+        }
+
+        if let Ok(path) = std::env::var("NONCE_ACCOUNTS_PATH") {
+            config.nonce_accounts_path = path;
+        }
+
+        if let Ok(address) = std::env::var("LOOKUP_TABLE_ADDRESS") {
+            config.lookup_table_address = Some(address);
+        }
+
         Ok(config)
     }
 
@@ -209,9 +696,102 @@ impl Config {
         if args.use_jito {
             self.jito_enabled = true;
         }
+
+        if args.use_tpu {
+            self.tpu_enabled = true;
+        }
+
+        if args.scoring {
+            self.scoring_enabled = true;
+        }
+
+        if let Some(threshold) = args.score_threshold {
+            self.score_threshold = threshold;
+        }
+
+        if let Some(rules) = &args.rules {
+            self.rules_path = Some(rules.clone());
+        }
     }
 }
 
+/// Parse a comma-separated list of regular expressions
+fn parse_regex_list(name: &str, value: &str) -> anyhow::Result<Vec<Regex>> {
+    value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|pattern| {
+            Regex::new(pattern)
+                .map_err(|e| anyhow::anyhow!("Invalid {} pattern '{}': {}", name, pattern, e))
+        })
+        .collect()
+}
+
+/// Parse `name=weight` pairs, e.g. `reputation=2,metadata=0.5`
+fn parse_weights(value: &str) -> anyhow::Result<HashMap<String, f64>> {
+    value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let (name, weight) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid FILTER_WEIGHTS entry '{}': expected name=weight", pair))?;
+            let weight = f64::from_str(weight.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid FILTER_WEIGHTS weight for '{}': {}", name, e))?;
+            Ok((name.trim().to_string(), weight))
+        })
+        .collect()
+}
+
+/// Parse `multiple:percent` take-profit tiers, e.g. `2:50,5:25`
+fn parse_take_profit_tiers(value: &str) -> anyhow::Result<Vec<(f64, f64)>> {
+    let mut tiers = value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|tier| {
+            let (multiple, percent) = tier
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid TAKE_PROFIT_TIERS entry '{}': expected multiple:percent", tier))?;
+            let multiple = f64::from_str(multiple.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid TAKE_PROFIT_TIERS multiple in '{}': {}", tier, e))?;
+            let percent = f64::from_str(percent.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid TAKE_PROFIT_TIERS percent in '{}': {}", tier, e))?;
+            if multiple <= 1.0 || !(0.0..=100.0).contains(&percent) {
+                anyhow::bail!("Invalid TAKE_PROFIT_TIERS entry '{}': multiple must exceed 1 and percent be 0 - 100", tier);
+            }
+            Ok((multiple, percent / 100.0))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    tiers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(tiers)
+}
+
+/// Parse `min_score:value` buckets, e.g. `0.8:30,0.5:15`, highest minimum score first
+fn parse_score_buckets(name: &str, value: &str) -> anyhow::Result<Vec<(f64, f64)>> {
+    let mut buckets = value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|bucket| {
+            let (min_score, setting) = bucket
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid {} entry '{}': expected min_score:value", name, bucket))?;
+            let min_score = f64::from_str(min_score.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid {} score in '{}': {}", name, bucket, e))?;
+            let setting = f64::from_str(setting.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid {} value in '{}': {}", name, bucket, e))?;
+            Ok((min_score, setting))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    buckets.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(buckets)
+}
+
 /// CLI arguments structure
 #[derive(Debug, Clone, clap::Parser)]
 #[command(name = "raydium-sniper-bot")]
@@ -253,7 +833,142 @@ pub struct CliArgs {
     #[arg(long)]
     pub use_jito: bool,
 
+    /// Send buys directly to leader TPUs over QUIC
+    #[arg(long)]
+    pub use_tpu: bool,
+
+    /// Enable weighted scoring with buy-size scaling
+    #[arg(long)]
+    pub scoring: bool,
+
+    /// Minimum weighted score required to buy
+    #[arg(long)]
+    pub score_threshold: Option<f64>,
+
+    /// Declarative filter rules file
+    #[arg(long)]
+    pub rules: Option<String>,
+
     /// Log level (trace, debug, info, warn, error)
     #[arg(long, default_value = "info")]
     pub log_level: String,
+
+    /// Run a maintenance command instead of the sniper loop
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Maintenance subcommands
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum Command {
+    /// Export the creator reputation store as JSON
+    ExportCreators {
+        /// Output file
+        path: String,
+    },
+    /// Merge creator history from a JSON export into the store
+    ImportCreators {
+        /// Input file
+        path: String,
+    },
+    /// Manage the durable nonce accounts used for pre-signed buys
+    Nonce {
+        #[command(subcommand)]
+        action: NonceCommand,
+    },
+    /// Manage the bot's address lookup table
+    LookupTable {
+        #[command(subcommand)]
+        action: LookupTableCommand,
+    },
+    /// Sell a tracked position
+    Sell {
+        /// Token mint of the position
+        mint: String,
+        /// Percentage of the position to sell
+        #[arg(long, default_value_t = 100.0)]
+        percent: f64,
+    },
+    /// Sell every tracked position
+    SellAll,
+    /// Close zero-balance token accounts and WSOL accounts, reclaiming their rent
+    CloseEmptyAccounts,
+    /// Print recorded price candles of a token as CSV
+    Candles {
+        /// Token mint
+        mint: String,
+        /// Candle width (1s or 1m)
+        #[arg(long, default_value = "1m")]
+        resolution: String,
+        /// Number of most recent candles to print
+        #[arg(long, default_value_t = 60)]
+        limit: usize,
+    },
+}
+
+/// Address lookup table management
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum LookupTableCommand {
+    /// Create a lookup table owned by the wallet and fill it with the static accounts
+    Create,
+    /// Add missing static accounts (and any extra addresses) to LOOKUP_TABLE_ADDRESS
+    Extend {
+        /// Extra addresses to add
+        addresses: Vec<String>,
+    },
+    /// Show the addresses held by LOOKUP_TABLE_ADDRESS
+    Show,
+}
+
+/// Durable nonce account management
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum NonceCommand {
+    /// Create and fund nonce accounts owned by the wallet
+    Create {
+        /// Number of accounts to create
+        #[arg(default_value_t = 1)]
+        count: u32,
+        /// Lamports to fund each account with beyond the rent-exempt minimum
+        #[arg(long, default_value_t = 0)]
+        extra_lamports: u64,
+    },
+    /// Show each nonce account's current nonce and balance
+    List,
+    /// Advance nonce accounts, invalidating anything signed against them
+    Advance {
+        /// Account to advance (default: all)
+        address: Option<String>,
+    },
+    /// Transfer lamports from the wallet to a nonce account
+    Fund {
+        /// Nonce account to fund
+        address: String,
+        /// Lamports to transfer
+        lamports: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_profit_tiers_are_sorted_fractions() {
+        let tiers = parse_take_profit_tiers("5:25, 2:50").unwrap();
+        assert_eq!(tiers, vec![(2.0, 0.5), (5.0, 0.25)]);
+    }
+
+    #[test]
+    fn empty_take_profit_tiers() {
+        assert!(parse_take_profit_tiers("").unwrap().is_empty());
+        assert!(parse_take_profit_tiers(" , ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_take_profit_tiers_are_rejected() {
+        assert!(parse_take_profit_tiers("2").is_err());
+        assert!(parse_take_profit_tiers("x:50").is_err());
+        assert!(parse_take_profit_tiers("1:50").is_err());
+        assert!(parse_take_profit_tiers("2:150").is_err());
+    }
 }
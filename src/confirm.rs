@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
+};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::TransactionError,
+};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use tokio::time::{sleep, Duration};
+
+/// How a transaction's landing was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationSource {
+    Geyser,
+    WebSocket,
+    Polling,
+    /// Reported landed by the Jito block engine
+    Bundle,
+}
+
+/// A pushed notification that a signature reached the target commitment
+#[derive(Debug, Clone)]
+pub struct SignatureNotice {
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    pub source: ConfirmationSource,
+}
+
+/// Notices kept for signatures nobody is watching yet, in case a landing is
+/// pushed before its sender starts watching
+const RECENT_NOTICES: usize = 256;
+
+/// First wait before resubscribing after the stream drops; doubles up to `MAX_RECONNECT_DELAY`
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Pushes confirmations of the wallet's transactions from one long-lived
+/// subscription: the Geyser transaction stream when configured, otherwise a
+/// PubSub `logsSubscribe` mentioning the wallet
+///
+/// Run `run` in the background. Whenever the subscription is (re)established,
+/// the signatures being watched are polled once, so landings during a gap are
+/// not missed. Callers should keep polling at a low rate while `is_live`, and
+/// at their full rate while it is not.
+pub struct SignatureWatcher {
+    geyser_url: Option<String>,
+    websocket_url: String,
+    commitment: CommitmentConfig,
+    wallet: Pubkey,
+    rpc_client: Arc<RpcClient>,
+    waiters: Mutex<HashMap<Signature, oneshot::Sender<SignatureNotice>>>,
+    /// Notices no one was waiting for, oldest first
+    recent: Mutex<VecDeque<(Signature, SignatureNotice)>>,
+    live: AtomicBool,
+}
+
+impl SignatureWatcher {
+    pub fn new(
+        geyser_url: Option<String>,
+        websocket_url: String,
+        commitment: CommitmentConfig,
+        wallet: Pubkey,
+        rpc_client: Arc<RpcClient>,
+    ) -> Self {
+        Self {
+            geyser_url,
+            websocket_url,
+            commitment,
+            wallet,
+            rpc_client,
+            waiters: Mutex::new(HashMap::new()),
+            recent: Mutex::new(VecDeque::new()),
+            live: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the subscription is currently established
+    pub fn is_live(&self) -> bool {
+        self.live.load(Ordering::Relaxed)
+    }
+
+    /// Start watching `signature`; the receiver resolves once it reaches the commitment
+    ///
+    /// Resolves at once if the landing was already pushed. Dropping the
+    /// receiver stops the watch.
+    pub fn watch(&self, signature: Signature) -> oneshot::Receiver<SignatureNotice> {
+        let (tx, rx) = oneshot::channel();
+
+        // Lock order matches `dispatch`, so a notice lands either in `recent` or with the waiter
+        let mut waiters = self.waiters.lock().unwrap();
+        let recent = self
+            .recent
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(seen, _)| *seen == signature)
+            .map(|(_, notice)| notice.clone());
+        match recent {
+            Some(notice) => {
+                let _ = tx.send(notice);
+            }
+            None => {
+                waiters.retain(|_, waiter| !waiter.is_closed());
+                waiters.insert(signature, tx);
+            }
+        }
+
+        rx
+    }
+
+    /// Keep the subscription up, resubscribing with backoff whenever it drops
+    pub async fn run(self: Arc<Self>) {
+        let mut delay = RECONNECT_DELAY;
+        loop {
+            let result = self.stream().await;
+            if self.live.swap(false, Ordering::Relaxed) {
+                delay = RECONNECT_DELAY;
+            }
+            if let Err(e) = result {
+                log::warn!(
+                    "Signature stream interrupted, resubscribing in {}s: {}",
+                    delay.as_secs(),
+                    e
+                );
+            }
+            sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    async fn stream(&self) -> Result<()> {
+        if let Some(ref grpc_url) = self.geyser_url {
+            match self.stream_geyser(grpc_url).await {
+                Ok(()) => return Ok(()),
+                Err(e) => log::debug!("Geyser signature stream failed, using WebSocket: {}", e),
+            }
+        }
+        self.stream_websocket().await
+    }
+
+    /// Mark the subscription live and resolve watches that landed while it was down
+    async fn subscribed(&self) {
+        self.live.store(true, Ordering::Relaxed);
+
+        let pending: Vec<Signature> = self.waiters.lock().unwrap().keys().copied().collect();
+        for chunk in pending.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+            let statuses = match self.rpc_client.get_signature_statuses(chunk).await {
+                Ok(response) => response.value,
+                Err(e) => {
+                    log::debug!("Failed to poll watched signatures: {}", e);
+                    continue;
+                }
+            };
+            for (signature, status) in chunk.iter().zip(statuses) {
+                let Some(status) = status else {
+                    continue;
+                };
+                if status.err.is_some() || status.satisfies_commitment(self.commitment) {
+                    self.dispatch(
+                        *signature,
+                        SignatureNotice {
+                            slot: status.slot,
+                            err: status.err,
+                            source: ConfirmationSource::Polling,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Hand a notice to its waiter, or keep it in case the watch comes later
+    fn dispatch(&self, signature: Signature, notice: SignatureNotice) {
+        let mut waiters = self.waiters.lock().unwrap();
+        if let Some(waiter) = waiters.remove(&signature) {
+            let _ = waiter.send(notice);
+            return;
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() >= RECENT_NOTICES {
+            recent.pop_front();
+        }
+        recent.push_back((signature, notice));
+    }
+
+    /// Stream the wallet's transactions from Geyser
+    async fn stream_geyser(&self, grpc_url: &str) -> Result<()> {
+        use yellowstone_grpc::{
+            geyser::SubscribeRequest,
+            proto::geyser::{CommitmentLevel as GeyserCommitment, SubscribeRequestFilterTransactions},
+        };
+
+        let mut client = yellowstone_grpc::GeyserGrpcClient::connect(grpc_url)
+            .await
+            .context("Failed to connect to Yellowstone Geyser")?;
+
+        let filter = SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: None,
+            signature: None,
+            account_include: vec![self.wallet.to_string()],
+            account_exclude: vec![],
+            account_required: vec![],
+        };
+
+        let geyser_commitment = match self.commitment.commitment {
+            CommitmentLevel::Processed => GeyserCommitment::Processed,
+            CommitmentLevel::Finalized => GeyserCommitment::Finalized,
+            _ => GeyserCommitment::Confirmed,
+        };
+
+        let request = SubscribeRequest {
+            slots: vec![],
+            accounts: vec![],
+            transactions: vec![filter],
+            transactions_status: vec![],
+            blocks: vec![],
+            blocks_meta: vec![],
+            accounts_data_slice: vec![],
+            commitment: Some(geyser_commitment as i32),
+        };
+
+        let mut stream = client
+            .subscribe_once(request)
+            .await
+            .context("Failed to subscribe to Geyser stream")?;
+        self.subscribed().await;
+
+        while let Some(update) = stream.message().await.transpose() {
+            let update = update.context("Error receiving Geyser update")?;
+            let Some(tx_update) = update.transaction else {
+                continue;
+            };
+            let Some(tx) = tx_update.transaction else {
+                continue;
+            };
+            let Ok(signature) = Signature::try_from(tx.signature.as_slice()) else {
+                continue;
+            };
+
+            // Geyser carries the transaction error bincode-encoded
+            let err = tx
+                .meta
+                .and_then(|meta| meta.err)
+                .and_then(|err| bincode::deserialize::<TransactionError>(&err.err).ok());
+
+            self.dispatch(
+                signature,
+                SignatureNotice {
+                    slot: tx_update.slot,
+                    err,
+                    source: ConfirmationSource::Geyser,
+                },
+            );
+        }
+
+        anyhow::bail!("Geyser stream ended")
+    }
+
+    /// Stream logs of transactions mentioning the wallet over PubSub
+    async fn stream_websocket(&self) -> Result<()> {
+        let client = PubsubClient::new(&self.websocket_url)
+            .await
+            .context("Failed to connect to PubSub")?;
+        let (mut notifications, _unsubscribe) = client
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![self.wallet.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.commitment),
+                },
+            )
+            .await
+            .context("logsSubscribe failed")?;
+        self.subscribed().await;
+
+        while let Some(response) = notifications.next().await {
+            let Ok(signature) = Signature::from_str(&response.value.signature) else {
+                continue;
+            };
+            self.dispatch(
+                signature,
+                SignatureNotice {
+                    slot: response.context.slot,
+                    err: response.value.err,
+                    source: ConfirmationSource::WebSocket,
+                },
+            );
+        }
+
+        anyhow::bail!("PubSub subscription closed")
+    }
+}
+
+/// Final state of a sent transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// Reached the target commitment without error
+    Landed {
+        signature: Signature,
+        slot: u64,
+        /// Time from first send to reaching the target commitment
+        latency: Duration,
+        source: ConfirmationSource,
+    },
+    /// Executed on-chain but returned an error (fees were still charged)
+    Failed {
+        signature: Signature,
+        error: TransactionError,
+    },
+    /// Never landed before its blockhash's `last_valid_block_height` passed
+    Expired { signature: Signature },
+}
+
+impl SendOutcome {
+    pub fn signature(&self) -> &Signature {
+        match self {
+            SendOutcome::Landed { signature, .. }
+            | SendOutcome::Failed { signature, .. }
+            | SendOutcome::Expired { signature } => signature,
+        }
+    }
+}
+
+impl fmt::Display for SendOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendOutcome::Landed {
+                signature,
+                slot,
+                latency,
+                source,
+            } => write!(
+                f,
+                "{} landed in slot {} after {:?} (via {:?})",
+                signature, slot, latency, source
+            ),
+            SendOutcome::Failed { signature, error } => {
+                write!(f, "{} failed on-chain: {}", signature, error)
+            }
+            SendOutcome::Expired { signature } => {
+                write!(f, "{} expired before landing", signature)
+            }
+        }
+    }
+}
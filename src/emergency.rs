@@ -0,0 +1,379 @@
+use anyhow::{Context, Result};
+use solana_sdk::{program_option::COption, pubkey::Pubkey};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep, Duration};
+
+use crate::config::Config;
+use crate::exits::{ExitEngine, ExitReason};
+use crate::positions::PositionStore;
+use crate::tracker::{PoolSnapshot, ReserveTracker, Withdrawal};
+
+/// How often the set of open positions is compared against the watched pools
+const RESYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Attempts at an emergency sell before the position may fire again
+const SELL_ATTEMPTS: u32 = 3;
+
+/// Wait between attempts at an emergency sell
+const SELL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Last seen state of the pool of one open position
+struct Watched {
+    mint: Pubkey,
+    sol_reserve: u64,
+    lp_supply: u64,
+    mint_authority: COption<Pubkey>,
+    freeze_authority: COption<Pubkey>,
+    /// Withdrawal seen in logs, to be sized by the LP supply drop it causes
+    pending_withdrawal: Option<String>,
+    /// An emergency exit is in flight for this position
+    fired: bool,
+}
+
+impl Watched {
+    fn new(mint: Pubkey, snapshot: &PoolSnapshot) -> Self {
+        Self {
+            mint,
+            sol_reserve: snapshot.reserves.sol,
+            lp_supply: snapshot.lp_supply,
+            pending_withdrawal: None,
+            mint_authority: snapshot.mint_authority,
+            freeze_authority: snapshot.freeze_authority,
+            fired: false,
+        }
+    }
+
+    /// Compare a new snapshot with the last one, returning the reason to exit if it looks like a rug
+    fn check(&mut self, snapshot: &PoolSnapshot, thresholds: &Thresholds) -> Option<ExitReason> {
+        let previous = std::mem::replace(&mut self.sol_reserve, snapshot.reserves.sol);
+        let lp_supply = std::mem::replace(&mut self.lp_supply, snapshot.lp_supply);
+        let mint_authority = std::mem::replace(&mut self.mint_authority, snapshot.mint_authority);
+        let freeze_authority =
+            std::mem::replace(&mut self.freeze_authority, snapshot.freeze_authority);
+
+        // Revoking an authority is harmless; setting or handing it over is not
+        let detail = match (snapshot.mint_authority, snapshot.freeze_authority) {
+            (COption::Some(new), _) if mint_authority != COption::Some(new) => {
+                Some(format!("mint authority changed to {}", new))
+            }
+            (_, COption::Some(new)) if freeze_authority != COption::Some(new) => {
+                Some(format!("freeze authority changed to {}", new))
+            }
+            _ => None,
+        };
+        if let Some(detail) = detail {
+            return Some(ExitReason::EmergencyAuthorityChange { detail });
+        }
+
+        if snapshot.lp_supply < lp_supply {
+            let withdrawn_pct = share_pct(lp_supply - snapshot.lp_supply, lp_supply);
+            let signature = self.pending_withdrawal.take();
+            if withdrawn_pct >= thresholds.withdraw_pct {
+                return Some(ExitReason::EmergencyWithdrawal {
+                    signature,
+                    withdrawn_pct,
+                });
+            }
+        }
+
+        let reserve = snapshot.reserves.sol;
+        if previous == 0 || reserve >= previous {
+            return None;
+        }
+        let drop_pct = (previous - reserve) as f64 / previous as f64 * 100.0;
+        (drop_pct >= thresholds.reserve_drop_pct)
+            .then_some(ExitReason::EmergencyReserveDrop { drop_pct })
+    }
+
+    /// Judge a withdrawal transaction, returning the reason to exit if it took enough liquidity
+    ///
+    /// Withdrawals seen only in logs are judged once the LP supply drop shows up in a snapshot.
+    fn withdrawal(&mut self, withdrawal: Withdrawal, thresholds: &Thresholds) -> Option<ExitReason> {
+        let Some(lp_amount) = withdrawal.lp_amount else {
+            self.pending_withdrawal = Some(withdrawal.signature);
+            return None;
+        };
+
+        let withdrawn_pct = share_pct(lp_amount, self.lp_supply);
+        if withdrawn_pct < thresholds.withdraw_pct {
+            log::info!(
+                "Ignoring withdrawal of {:.1}% of liquidity from the pool of {} in {}",
+                withdrawn_pct,
+                self.mint,
+                withdrawal.signature
+            );
+            return None;
+        }
+        Some(ExitReason::EmergencyWithdrawal {
+            signature: Some(withdrawal.signature),
+            withdrawn_pct,
+        })
+    }
+}
+
+/// `part` as a percentage of `whole`; all of it when the whole is unknown
+fn share_pct(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        100.0
+    } else {
+        (part as f64 / whole as f64 * 100.0).min(100.0)
+    }
+}
+
+/// What counts as a rug, in percent
+struct Thresholds {
+    reserve_drop_pct: f64,
+    withdraw_pct: f64,
+}
+
+/// Watches the pool and mint of every open position and sells immediately on
+/// a large liquidity withdrawal, a sharp reserve drop or an authority change
+///
+/// Follows the reserve tracker's snapshots and withdrawal feed rather than
+/// subscribing on its own, so each pool is streamed once.
+pub struct EmergencyWatcher {
+    exits: Arc<ExitEngine>,
+    positions: Arc<PositionStore>,
+    tracker: Arc<ReserveTracker>,
+    thresholds: Thresholds,
+}
+
+impl EmergencyWatcher {
+    pub fn new(
+        exits: Arc<ExitEngine>,
+        positions: Arc<PositionStore>,
+        tracker: Arc<ReserveTracker>,
+        config: &Config,
+    ) -> Self {
+        Self {
+            exits,
+            positions,
+            tracker,
+            thresholds: Thresholds {
+                reserve_drop_pct: config.emergency_reserve_drop_pct,
+                withdraw_pct: config.emergency_withdraw_pct,
+            },
+        }
+    }
+
+    /// Keep the watched pools in line with the open positions and react to their updates
+    pub async fn run(self: Arc<Self>) {
+        let mut updates = self.tracker.updates();
+        let mut withdrawals = self.tracker.withdrawals();
+        let mut ticker = interval(RESYNC_INTERVAL);
+        let mut watched: HashMap<Pubkey, Watched> = HashMap::new();
+        // Pools whose emergency sell failed for good, so they may fire again
+        let (failed_tx, mut failed) = mpsc::unbounded_channel();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(e) = self.sync(&mut watched).await {
+                        log::warn!("Failed to update emergency watch: {}", e);
+                    }
+                }
+                Some(pool) = failed.recv() => {
+                    if let Some(position) = watched.get_mut(&pool) {
+                        position.fired = false;
+                    }
+                }
+                update = updates.recv() => match update {
+                    Ok((pool, snapshot)) => {
+                        let reason = watched
+                            .get_mut(&pool)
+                            .filter(|w| !w.fired)
+                            .and_then(|w| w.check(&snapshot, &self.thresholds));
+                        self.fire(pool, reason, &mut watched, &failed_tx);
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Emergency watcher fell behind, skipped {} reserve updates", skipped);
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                withdrawal = withdrawals.recv() => match withdrawal {
+                    Ok(withdrawal) => {
+                        let pool = withdrawal.pool;
+                        let reason = watched
+                            .get_mut(&pool)
+                            .filter(|w| !w.fired)
+                            .and_then(|w| w.withdrawal(withdrawal, &self.thresholds));
+                        self.fire(pool, reason, &mut watched, &failed_tx);
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Emergency watcher fell behind, skipped {} withdrawals", skipped);
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+            }
+        }
+    }
+
+    /// Start watching pools of new positions and stop watching closed ones
+    async fn sync(&self, watched: &mut HashMap<Pubkey, Watched>) -> Result<()> {
+        let positions: Vec<_> = self
+            .positions
+            .all()?
+            .into_iter()
+            .filter(|position| position.token_amount > 0)
+            .collect();
+
+        let before = watched.len();
+        let mut open = HashSet::new();
+        let mut added = 0;
+        for position in positions {
+            let pool = Pubkey::from_str(&position.pool).context("Invalid position pool")?;
+            open.insert(pool);
+            if watched.contains_key(&pool) {
+                continue;
+            }
+
+            // Tracks the pool if the exit engine hasn't yet
+            self.exits.pool_keys(&position).await?;
+            let snapshot = *self
+                .tracker
+                .subscribe(&pool)
+                .with_context(|| format!("Pool {} is no longer tracked", pool))?
+                .borrow();
+            let mint = Pubkey::from_str(&position.mint).context("Invalid position mint")?;
+            watched.insert(pool, Watched::new(mint, &snapshot));
+            added += 1;
+        }
+
+        watched.retain(|pool, _| open.contains(pool));
+        if added > 0 || watched.len() != before + added {
+            log::info!("Emergency watcher covering {} positions", watched.len());
+        }
+        Ok(())
+    }
+
+    /// Sell the position in a pool's mint if there is a reason to, retrying a few times
+    ///
+    /// If every attempt fails the pool is reported on `failed`, so the next sign
+    /// of a rug fires again rather than the position being left unwatched.
+    fn fire(
+        &self,
+        pool: Pubkey,
+        reason: Option<ExitReason>,
+        watched: &mut HashMap<Pubkey, Watched>,
+        failed: &mpsc::UnboundedSender<Pubkey>,
+    ) {
+        let (Some(reason), Some(position)) = (reason, watched.get_mut(&pool)) else {
+            return;
+        };
+        position.fired = true;
+        let mint = position.mint;
+
+        log::warn!("Emergency exit from {}: {}", mint, reason);
+        let exits = self.exits.clone();
+        let failed = failed.clone();
+        tokio::spawn(async move {
+            for attempt in 1..=SELL_ATTEMPTS {
+                match exits.exit(&mint.to_string(), None, reason.clone()).await {
+                    Ok(_) => return,
+                    Err(e) => log::error!(
+                        "Emergency sell of {} failed (attempt {}/{}): {}",
+                        mint,
+                        attempt,
+                        SELL_ATTEMPTS,
+                        e
+                    ),
+                }
+                if attempt < SELL_ATTEMPTS {
+                    sleep(SELL_RETRY_DELAY).await;
+                }
+            }
+            let _ = failed.send(pool);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::Reserves;
+    use tokio::time::Instant;
+
+    const THRESHOLDS: Thresholds = Thresholds {
+        reserve_drop_pct: 30.0,
+        withdraw_pct: 10.0,
+    };
+
+    fn snapshot(sol: u64, lp_supply: u64) -> PoolSnapshot {
+        PoolSnapshot {
+            slot: 1,
+            reserves: Reserves { sol, token: 1_000_000 },
+            price: 0.0,
+            mint_authority: COption::None,
+            freeze_authority: COption::None,
+            lp_supply,
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn withdrawal(lp_amount: Option<u64>) -> Withdrawal {
+        Withdrawal {
+            pool: Pubkey::new_unique(),
+            signature: "sig".to_string(),
+            lp_amount,
+        }
+    }
+
+    #[test]
+    fn small_withdrawal_is_ignored() {
+        let mut watched = Watched::new(Pubkey::new_unique(), &snapshot(100, 1_000));
+        assert!(watched.withdrawal(withdrawal(Some(50)), &THRESHOLDS).is_none());
+    }
+
+    #[test]
+    fn large_withdrawal_fires() {
+        let mut watched = Watched::new(Pubkey::new_unique(), &snapshot(100, 1_000));
+        let reason = watched.withdrawal(withdrawal(Some(1_000)), &THRESHOLDS);
+        assert!(matches!(
+            reason,
+            Some(ExitReason::EmergencyWithdrawal { withdrawn_pct, .. }) if withdrawn_pct == 100.0
+        ));
+    }
+
+    #[test]
+    fn withdrawal_seen_in_logs_is_sized_by_lp_supply_drop() {
+        let mut watched = Watched::new(Pubkey::new_unique(), &snapshot(100, 1_000));
+        assert!(watched.withdrawal(withdrawal(None), &THRESHOLDS).is_none());
+
+        let reason = watched.check(&snapshot(95, 800), &THRESHOLDS);
+        assert!(matches!(
+            reason,
+            Some(ExitReason::EmergencyWithdrawal { signature: Some(ref sig), withdrawn_pct })
+                if sig == "sig" && (withdrawn_pct - 20.0).abs() < 1e-9
+        ));
+    }
+
+    #[test]
+    fn reserve_drop_fires_past_threshold() {
+        let mut watched = Watched::new(Pubkey::new_unique(), &snapshot(100, 1_000));
+        assert!(watched.check(&snapshot(80, 1_000), &THRESHOLDS).is_none());
+        assert!(matches!(
+            watched.check(&snapshot(40, 1_000), &THRESHOLDS),
+            Some(ExitReason::EmergencyReserveDrop { .. })
+        ));
+    }
+
+    #[test]
+    fn revoking_an_authority_is_harmless() {
+        let authority = Pubkey::new_unique();
+        let mut before = snapshot(100, 1_000);
+        before.mint_authority = COption::Some(authority);
+        let mut watched = Watched::new(Pubkey::new_unique(), &before);
+        assert!(watched.check(&snapshot(100, 1_000), &THRESHOLDS).is_none());
+
+        let mut handed_over = snapshot(100, 1_000);
+        handed_over.freeze_authority = COption::Some(authority);
+        assert!(matches!(
+            watched.check(&handed_over, &THRESHOLDS),
+            Some(ExitReason::EmergencyAuthorityChange { .. })
+        ));
+    }
+}
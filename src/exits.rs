@@ -0,0 +1,705 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::config::Config;
+use crate::pool::PoolKeys;
+use crate::positions::{Position, PositionStore};
+use crate::sniper::Sniper;
+use crate::tracker::ReserveTracker;
+use crate::trades::{SellRecord, TradeLog};
+
+/// Wait after a failed sell before the rules may retry it, doubled per consecutive failure
+const SELL_RETRY_BASE: Duration = Duration::from_secs(5);
+
+/// Longest wait between retries of a failing sell
+const SELL_RETRY_MAX: Duration = Duration::from_secs(300);
+
+/// Positions checked (and sold) at once, so one slow sell doesn't hold up the rest
+const CHECK_CONCURRENCY: usize = 8;
+
+/// Oldest tracked snapshot a position is priced from before its pool is re-read over RPC
+const MAX_SNAPSHOT_AGE: Duration = Duration::from_secs(10);
+
+/// Why a position (or part of it) was sold; `code` is the reason code in the trade log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ExitReason {
+    /// Take-profit tiers up to `tier` (1-based) were reached at `multiple` of cost
+    TakeProfit { tier: usize, multiple: f64 },
+    /// Value fell to `multiple` of cost
+    StopLoss { multiple: f64 },
+    /// Value fell `drawdown_pct` below its peak of `peak_lamports`
+    TrailingStop { peak_lamports: u64, drawdown_pct: f64 },
+    /// The position reached its maximum hold time
+    MaxHoldTime { held_secs: u64 },
+    /// `withdrawn_pct` of the pool's liquidity was withdrawn, in `signature` if it was seen
+    EmergencyWithdrawal {
+        signature: Option<String>,
+        #[serde(default)]
+        withdrawn_pct: f64,
+    },
+    /// The pool's SOL reserve fell `drop_pct` in a single update
+    EmergencyReserveDrop { drop_pct: f64 },
+    /// The token's mint or freeze authority changed
+    EmergencyAuthorityChange { detail: String },
+    /// Sold from the command line
+    Manual { percent: f64 },
+}
+
+impl ExitReason {
+    /// Emergency exits are sold immediately at maximum priority
+    pub fn is_emergency(&self) -> bool {
+        matches!(
+            self,
+            Self::EmergencyWithdrawal { .. }
+                | Self::EmergencyReserveDrop { .. }
+                | Self::EmergencyAuthorityChange { .. }
+        )
+    }
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TakeProfit { tier, multiple } => {
+                write!(f, "take-profit tier {} at {:.2}x", tier, multiple)
+            }
+            Self::StopLoss { multiple } => write!(f, "stop-loss at {:.2}x", multiple),
+            Self::TrailingStop {
+                peak_lamports,
+                drawdown_pct,
+            } => write!(
+                f,
+                "trailing stop, {:.1}% below peak of {:.4} SOL",
+                drawdown_pct,
+                *peak_lamports as f64 / 1_000_000_000.0
+            ),
+            Self::MaxHoldTime { held_secs } => write!(f, "max hold time after {}s", held_secs),
+            Self::EmergencyWithdrawal {
+                signature,
+                withdrawn_pct,
+            } => {
+                write!(f, "emergency: {:.1}% of liquidity withdrawn", withdrawn_pct)?;
+                match signature {
+                    Some(signature) => write!(f, " in {}", signature),
+                    None => Ok(()),
+                }
+            }
+            Self::EmergencyReserveDrop { drop_pct } => {
+                write!(f, "emergency: SOL reserve dropped {:.1}%", drop_pct)
+            }
+            Self::EmergencyAuthorityChange { detail } => {
+                write!(f, "emergency: {}", detail)
+            }
+            Self::Manual { percent } => write!(f, "manual sell of {}%", percent),
+        }
+    }
+}
+
+/// Sell `fraction` of the initial position once its value reaches `multiple` of cost
+#[derive(Debug, Clone, Copy)]
+pub struct TakeProfitTier {
+    pub multiple: f64,
+    pub fraction: f64,
+}
+
+/// A setting chosen by the pool's score at entry
+///
+/// The first bucket whose minimum score the entry score reaches applies;
+/// positions below every bucket, or without a score, use the default.
+#[derive(Debug, Clone)]
+pub struct ScoreBuckets<T> {
+    pub default: Option<T>,
+    /// (minimum score, setting), highest minimum first
+    pub buckets: Vec<(f64, T)>,
+}
+
+impl<T: Copy> ScoreBuckets<T> {
+    pub fn for_score(&self, score: Option<f64>) -> Option<T> {
+        score
+            .and_then(|score| {
+                self.buckets
+                    .iter()
+                    .find(|(min_score, _)| score >= *min_score)
+                    .map(|(_, setting)| *setting)
+            })
+            .or(self.default)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.buckets.is_empty()
+    }
+}
+
+/// When to sell held positions
+#[derive(Debug, Clone)]
+pub struct ExitRules {
+    /// Sorted by ascending multiple
+    pub take_profits: Vec<TakeProfitTier>,
+    /// Sell everything at or below this multiple of cost
+    pub stop_loss_multiple: Option<f64>,
+    /// Percentage below the peak value at which everything is sold
+    pub trailing_stop_pct: ScoreBuckets<f64>,
+    /// Seconds after which everything is sold
+    pub max_hold_secs: ScoreBuckets<u64>,
+}
+
+impl ExitRules {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            take_profits: config
+                .take_profit_tiers
+                .iter()
+                .map(|&(multiple, fraction)| TakeProfitTier { multiple, fraction })
+                .collect(),
+            stop_loss_multiple: config.stop_loss_pct.map(|pct| 1.0 - pct / 100.0),
+            trailing_stop_pct: ScoreBuckets {
+                default: config.trailing_stop_pct,
+                buckets: config.trailing_stop_buckets.clone(),
+            },
+            max_hold_secs: ScoreBuckets {
+                default: config.max_hold_secs,
+                buckets: config.max_hold_buckets.clone(),
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.take_profits.is_empty()
+            && self.stop_loss_multiple.is_none()
+            && self.trailing_stop_pct.is_empty()
+            && self.max_hold_secs.is_empty()
+    }
+
+    /// Tokens to sell, and why, for a position whose tokens are currently worth `value` lamports
+    ///
+    /// Full exits (stop-loss, trailing stop, max hold time) take precedence.
+    /// Tiers passed since the last check are sold together, so a jump straight
+    /// from 1x to 6x sells both the 2x and 5x fractions.
+    pub fn check(&self, position: &Position, value: u64) -> Option<(u64, ExitReason)> {
+        let multiple = value as f64 / position.cost_basis_lamports.max(1) as f64;
+
+        if let Some(stop) = self.stop_loss_multiple {
+            if multiple <= stop {
+                return Some((position.token_amount, ExitReason::StopLoss { multiple }));
+            }
+        }
+
+        if let Some(trail_pct) = self.trailing_stop_pct.for_score(position.entry_score) {
+            let peak = position.peak_value_lamports;
+            if peak > 0 {
+                let drawdown_pct = (1.0 - value as f64 / peak as f64) * 100.0;
+                if drawdown_pct >= trail_pct {
+                    return Some((
+                        position.token_amount,
+                        ExitReason::TrailingStop {
+                            peak_lamports: peak,
+                            drawdown_pct,
+                        },
+                    ));
+                }
+            }
+        }
+
+        if let Some(max_hold) = self.max_hold_secs.for_score(position.entry_score) {
+            let held_secs = position.held_secs();
+            if held_secs >= max_hold {
+                return Some((position.token_amount, ExitReason::MaxHoldTime { held_secs }));
+            }
+        }
+
+        let reached = self
+            .take_profits
+            .iter()
+            .take_while(|tier| multiple >= tier.multiple)
+            .count();
+        if reached <= position.take_profits_hit {
+            return None;
+        }
+
+        let fraction: f64 = self.take_profits[position.take_profits_hit..reached]
+            .iter()
+            .map(|tier| tier.fraction)
+            .sum();
+        let amount = ((position.initial_amount() as f64 * fraction) as u64).min(position.token_amount);
+        if amount == 0 {
+            return None;
+        }
+
+        Some((
+            amount,
+            ExitReason::TakeProfit {
+                tier: reached,
+                multiple,
+            },
+        ))
+    }
+}
+
+/// Watches held positions and sells them when an exit rule triggers
+pub struct ExitEngine {
+    sniper: Arc<Sniper>,
+    positions: Arc<PositionStore>,
+    trade_log: Arc<TradeLog>,
+    rules: ExitRules,
+    dry_run: bool,
+    /// Streams pool reserves, so re-valuing a position needs no RPC call
+    tracker: Arc<ReserveTracker>,
+    /// Positions as simulated exits left them, by mint; dry runs never touch the store
+    paper_positions: Mutex<HashMap<String, Position>>,
+    /// Per-mint locks, held across a sell so two triggers can't sell the same tokens
+    mint_locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    /// Mints whose last sell failed: (consecutive failures, earliest rule-driven retry)
+    failed_sells: Mutex<HashMap<String, (u32, Instant)>>,
+}
+
+impl ExitEngine {
+    pub fn new(
+        sniper: Arc<Sniper>,
+        positions: Arc<PositionStore>,
+        trade_log: Arc<TradeLog>,
+        tracker: Arc<ReserveTracker>,
+        config: &Config,
+    ) -> Self {
+        Self {
+            sniper,
+            positions,
+            trade_log,
+            tracker,
+            rules: ExitRules::from_config(config),
+            dry_run: config.dry_run,
+            paper_positions: Mutex::new(HashMap::new()),
+            mint_locks: Mutex::new(HashMap::new()),
+            failed_sells: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn rules(&self) -> &ExitRules {
+        &self.rules
+    }
+
+    /// Re-value every held position each `interval` and sell those that hit an exit rule
+    ///
+    /// Positions are checked concurrently; the per-mint lock keeps sells of one mint in order.
+    pub async fn run(self: Arc<Self>, interval: Duration) {
+        loop {
+            match self.positions.all() {
+                Ok(positions) => {
+                    stream::iter(positions)
+                        .for_each_concurrent(CHECK_CONCURRENCY, |position| {
+                            let engine = &self;
+                            async move {
+                                if let Err(e) = engine.check_position(&position.mint).await {
+                                    log::warn!("Exit check failed for {}: {}", position.mint, e);
+                                }
+                            }
+                        })
+                        .await;
+                }
+                Err(e) => log::warn!("Failed to load positions: {}", e),
+            }
+            sleep(interval).await;
+        }
+    }
+
+    /// The position in `mint` as the engine sees it, including simulated exits in dry-run mode
+    pub fn position(&self, mint: &str) -> Result<Option<Position>> {
+        if self.dry_run {
+            if let Some(paper) = self.paper_positions.lock().unwrap().get(mint) {
+                return Ok(Some(paper.clone()));
+            }
+        }
+        self.positions.get(mint)
+    }
+
+    /// Lock serializing all reads-then-writes of one mint's position
+    fn mint_lock(&self, mint: &str) -> Arc<AsyncMutex<()>> {
+        self.mint_locks
+            .lock()
+            .unwrap()
+            .entry(mint.to_string())
+            .or_default()
+            .clone()
+    }
+
+    async fn check_position(&self, mint: &str) -> Result<()> {
+        let lock = self.mint_lock(mint);
+        let _guard = lock.lock().await;
+
+        let Some(mut position) = self.position(mint)? else {
+            return Ok(());
+        };
+        if position.token_amount == 0 || position.cost_basis_lamports == 0 {
+            return Ok(());
+        }
+
+        let value = self.position_value(&position).await?;
+        log::debug!(
+            "{} worth {:.4} SOL ({:.2}x, peak {:.4} SOL)",
+            position.mint,
+            value as f64 / 1_000_000_000.0,
+            value as f64 / position.cost_basis_lamports as f64,
+            position.peak_value_lamports as f64 / 1_000_000_000.0
+        );
+
+        // Raise the high-water mark before checking, and persist it so restarts keep the trail
+        if value > position.peak_value_lamports {
+            position.peak_value_lamports = value;
+            self.modify(&position, |stored| {
+                stored.peak_value_lamports = stored.peak_value_lamports.max(value)
+            })?;
+        }
+
+        if let Some((amount, reason)) = self.rules.check(&position, value) {
+            if let Some(retry_at) = self.retry_at(mint) {
+                if Instant::now() < retry_at {
+                    log::debug!("Holding off {} on {} after failed sells", reason, mint);
+                    return Ok(());
+                }
+            }
+            self.sell(&position, amount, reason).await?;
+        }
+
+        Ok(())
+    }
+
+    /// When rule-driven sells of `mint` may be retried, if its last sell failed
+    fn retry_at(&self, mint: &str) -> Option<Instant> {
+        self.failed_sells
+            .lock()
+            .unwrap()
+            .get(mint)
+            .map(|(_, retry_at)| *retry_at)
+    }
+
+    /// Back off exponentially after a failed sell; returns the wait before the next try
+    fn record_failed_sell(&self, mint: &str) -> Duration {
+        let mut failed = self.failed_sells.lock().unwrap();
+        let entry = failed.entry(mint.to_string()).or_insert((0, Instant::now()));
+        entry.0 += 1;
+        let wait = SELL_RETRY_BASE
+            .saturating_mul(1 << (entry.0 - 1).min(16))
+            .min(SELL_RETRY_MAX);
+        entry.1 = Instant::now() + wait;
+        wait
+    }
+
+    /// Apply `f` to a position and persist it, or keep it in memory in dry-run mode
+    ///
+    /// Store writes go through a compare-and-swap on the current record, so a
+    /// buy adding to the position meanwhile is kept; a position left with no
+    /// tokens is removed. Returns the position as stored, `None` once removed.
+    fn modify(&self, position: &Position, mut f: impl FnMut(&mut Position)) -> Result<Option<Position>> {
+        if self.dry_run {
+            let mut paper = position.clone();
+            f(&mut paper);
+            self.paper_positions
+                .lock()
+                .unwrap()
+                .insert(paper.mint.clone(), paper.clone());
+            return Ok(Some(paper));
+        }
+
+        self.positions.update(&position.mint, |current| {
+            let mut stored = current?;
+            f(&mut stored);
+            (stored.token_amount > 0).then_some(stored)
+        })
+    }
+
+    /// Decoded keys of a position's pool, tracking the pool if it isn't already
+    pub async fn pool_keys(&self, position: &Position) -> Result<PoolKeys> {
+        let pool = Pubkey::from_str(&position.pool).context("Invalid position pool")?;
+        self.tracker.track(&pool, position.pool_type).await?;
+        self.tracker
+            .keys(&pool)
+            .with_context(|| format!("Pool {} is no longer tracked", pool))
+    }
+
+    /// SOL the held tokens would sell for against the tracked reserves, in lamports
+    ///
+    /// A snapshot the tracker hasn't updated for `MAX_SNAPSHOT_AGE` is replaced
+    /// by an RPC read, so a stalled stream never leaves the rules on frozen reserves.
+    async fn position_value(&self, position: &Position) -> Result<u64> {
+        let pool = Pubkey::from_str(&position.pool).context("Invalid position pool")?;
+        let mut snapshot = *self.tracker.track(&pool, position.pool_type).await?.borrow();
+        let age = snapshot.updated_at.elapsed();
+        if age > MAX_SNAPSHOT_AGE {
+            log::debug!("Reserves of {} are {}s old, re-reading", pool, age.as_secs());
+            snapshot = self.tracker.refresh(&pool).await.with_context(|| {
+                format!("Reserves of {} are {}s old and could not be re-read", pool, age.as_secs())
+            })?;
+        }
+        Ok(snapshot.reserves.sell_out(position.token_amount))
+    }
+
+    /// Sell `amount` of the position in `mint` (everything if `None`)
+    ///
+    /// Waits for any exit already in flight for the mint and re-reads the
+    /// position afterwards, so concurrent triggers never sell the same tokens twice.
+    ///
+    /// Returns the logged sell, or `None` if there was nothing left to sell.
+    pub async fn exit(
+        &self,
+        mint: &str,
+        amount: Option<u64>,
+        reason: ExitReason,
+    ) -> Result<Option<SellRecord>> {
+        let lock = self.mint_lock(mint);
+        let _guard = lock.lock().await;
+
+        let Some(position) = self.position(mint)? else {
+            log::info!("No open position in {}, skipping {}", mint, reason);
+            return Ok(None);
+        };
+        if position.token_amount == 0 {
+            return Ok(None);
+        }
+
+        let amount = amount
+            .unwrap_or(position.token_amount)
+            .min(position.token_amount);
+        self.sell(&position, amount, reason).await.map(Some)
+    }
+
+    /// Sell `amount` of a position, update or close it, and log the sell
+    ///
+    /// The caller must hold the mint's lock.
+    async fn sell(&self, position: &Position, amount: u64, reason: ExitReason) -> Result<SellRecord> {
+        log::info!("Exiting {} of {}: {}", amount, position.mint, reason);
+
+        let receipt = match self.sniper.execute_sell(position, amount, &reason).await {
+            Ok(receipt) => {
+                self.failed_sells.lock().unwrap().remove(&position.mint);
+                receipt
+            }
+            Err(e) => {
+                let wait = self.record_failed_sell(&position.mint);
+                return Err(e.context(format!("sell failed, rules retry in {}s", wait.as_secs())));
+            }
+        };
+
+        // Sell what the fill says actually left the wallet, falling back to the requested amount
+        let sold = receipt
+            .fill
+            .as_ref()
+            .map(|fill| fill.token_delta.unsigned_abs().min(u64::MAX as u128) as u64)
+            .filter(|sold| *sold > 0)
+            .unwrap_or(amount);
+
+        let tier = match reason {
+            ExitReason::TakeProfit { tier, .. } => Some(tier),
+            _ => None,
+        };
+        let updated = self.modify(position, |stored| {
+            if stored.initial_token_amount == 0 {
+                stored.initial_token_amount = stored.token_amount;
+            }
+            stored.reduce(sold);
+            if let Some(tier) = tier {
+                stored.take_profits_hit = stored.take_profits_hit.max(tier);
+            }
+        })?;
+
+        match updated {
+            Some(updated) => log::info!("Position now {}", updated),
+            None => {
+                if let Ok(pool) = Pubkey::from_str(&position.pool) {
+                    // Keep recording its price for a while after the exit
+                    self.tracker.release(&pool);
+                }
+                log::info!("Closed position in {}", position.mint);
+            }
+        }
+
+        let record = SellRecord::new(position, &receipt, sold, reason, self.dry_run);
+        if let Err(e) = self.trade_log.append(&record) {
+            log::warn!("Failed to record sell: {}", e);
+        }
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(cost: u64, peak: u64) -> Position {
+        Position {
+            mint: "mint".to_string(),
+            pool: "pool".to_string(),
+            pool_type: crate::detector::PoolType::AMMv4,
+            token_amount: 1_000,
+            decimals: 6,
+            cost_basis_lamports: cost,
+            entry_slot: 1,
+            entry_signature: "sig".to_string(),
+            opened_at: chrono::Utc::now().timestamp(),
+            initial_token_amount: 1_000,
+            take_profits_hit: 0,
+            entry_score: None,
+            peak_value_lamports: peak,
+            untracked_tokens: 0,
+        }
+    }
+
+    fn rules() -> ExitRules {
+        ExitRules {
+            take_profits: vec![
+                TakeProfitTier {
+                    multiple: 2.0,
+                    fraction: 0.5,
+                },
+                TakeProfitTier {
+                    multiple: 5.0,
+                    fraction: 0.25,
+                },
+            ],
+            stop_loss_multiple: Some(0.5),
+            trailing_stop_pct: ScoreBuckets {
+                default: None,
+                buckets: vec![],
+            },
+            max_hold_secs: ScoreBuckets {
+                default: None,
+                buckets: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn holds_between_stop_loss_and_first_tier() {
+        assert!(rules().check(&position(1_000, 0), 1_500).is_none());
+    }
+
+    #[test]
+    fn sells_first_tier_fraction() {
+        let (amount, reason) = rules().check(&position(1_000, 0), 2_000).unwrap();
+        assert_eq!(amount, 500);
+        assert!(matches!(reason, ExitReason::TakeProfit { tier: 1, .. }));
+    }
+
+    #[test]
+    fn tiers_passed_at_once_are_sold_together() {
+        let (amount, reason) = rules().check(&position(1_000, 0), 6_000).unwrap();
+        assert_eq!(amount, 750);
+        assert!(matches!(reason, ExitReason::TakeProfit { tier: 2, .. }));
+    }
+
+    #[test]
+    fn tiers_already_sold_are_not_sold_again() {
+        let mut held = position(500, 0);
+        held.token_amount = 500;
+        held.take_profits_hit = 1;
+        assert!(rules().check(&held, 1_500).is_none());
+
+        let (amount, reason) = rules().check(&held, 3_000).unwrap();
+        assert_eq!(amount, 250);
+        assert!(matches!(reason, ExitReason::TakeProfit { tier: 2, .. }));
+    }
+
+    #[test]
+    fn stop_loss_sells_everything() {
+        let (amount, reason) = rules().check(&position(1_000, 0), 400).unwrap();
+        assert_eq!(amount, 1_000);
+        assert!(matches!(reason, ExitReason::StopLoss { .. }));
+    }
+
+    #[test]
+    fn stop_loss_is_checked_before_other_exits() {
+        let mut rules = rules();
+        rules.trailing_stop_pct.default = Some(10.0);
+        rules.max_hold_secs.default = Some(0);
+
+        let (_, reason) = rules.check(&position(1_000, 2_000), 400).unwrap();
+        assert!(matches!(reason, ExitReason::StopLoss { .. }));
+    }
+
+    fn trailing_rules(trail_pct: f64, max_hold_secs: u64) -> ExitRules {
+        ExitRules {
+            trailing_stop_pct: ScoreBuckets {
+                default: Some(trail_pct),
+                buckets: vec![],
+            },
+            max_hold_secs: ScoreBuckets {
+                default: Some(max_hold_secs),
+                buckets: vec![],
+            },
+            ..rules()
+        }
+    }
+
+    fn buckets() -> ScoreBuckets<u64> {
+        ScoreBuckets {
+            default: Some(60),
+            buckets: vec![(0.8, 600), (0.5, 300)],
+        }
+    }
+
+    #[test]
+    fn score_picks_first_bucket_reached() {
+        assert_eq!(buckets().for_score(Some(0.9)), Some(600));
+        assert_eq!(buckets().for_score(Some(0.8)), Some(600));
+        assert_eq!(buckets().for_score(Some(0.6)), Some(300));
+    }
+
+    #[test]
+    fn score_below_every_bucket_uses_default() {
+        assert_eq!(buckets().for_score(Some(0.2)), Some(60));
+    }
+
+    #[test]
+    fn missing_score_uses_default() {
+        assert_eq!(buckets().for_score(None), Some(60));
+
+        let no_default = ScoreBuckets {
+            default: None,
+            ..buckets()
+        };
+        assert_eq!(no_default.for_score(None), None);
+        assert_eq!(no_default.for_score(Some(0.2)), None);
+    }
+
+    #[test]
+    fn unvalued_position_has_no_trail() {
+        // A peak of 0 means the position hasn't been valued yet
+        let rules = trailing_rules(10.0, 3_600);
+        assert!(rules.check(&position(1_000, 0), 800).is_none());
+    }
+
+    #[test]
+    fn trailing_stop_sells_everything_below_peak() {
+        let rules = trailing_rules(20.0, 3_600);
+        // Below the first take-profit tier either way
+        assert!(rules.check(&position(2_000, 3_000), 2_500).is_none());
+
+        let (amount, reason) = rules.check(&position(2_000, 3_000), 2_400).unwrap();
+        assert_eq!(amount, 1_000);
+        assert!(matches!(reason, ExitReason::TrailingStop { peak_lamports: 3_000, .. }));
+    }
+
+    #[test]
+    fn trailing_stop_is_checked_before_max_hold_and_take_profit() {
+        let rules = trailing_rules(20.0, 0);
+        // 2.4x is past the first tier and the hold time is up, but the trail triggers first
+        let (_, reason) = rules.check(&position(1_000, 3_000), 2_400).unwrap();
+        assert!(matches!(reason, ExitReason::TrailingStop { .. }));
+    }
+
+    #[test]
+    fn max_hold_is_checked_before_take_profit() {
+        let rules = trailing_rules(50.0, 0);
+        let (amount, reason) = rules.check(&position(1_000, 2_000), 2_000).unwrap();
+        assert_eq!(amount, 1_000);
+        assert!(matches!(reason, ExitReason::MaxHoldTime { .. }));
+    }
+}
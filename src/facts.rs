@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use solana_sdk::program_pack::Pack;
+use spl_token::state::Mint;
+use std::sync::Arc;
+
+use crate::filter::FilterContext;
+use crate::metadata::TokenMetadata;
+use crate::pool::WSOL_MINT;
+use crate::reputation::CreatorStore;
+use crate::utils;
+
+/// Type of a pool fact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactType {
+    Bool,
+    Number,
+}
+
+/// Value of a pool fact
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FactValue {
+    Bool(bool),
+    Number(f64),
+}
+
+/// Everything known about a pool at evaluation time, as seen by the rule language
+#[derive(Debug, Clone, Default)]
+pub struct PoolFacts {
+    /// SOL in the pool's WSOL vault
+    pub liquidity_sol: f64,
+    /// USD value of both sides of the pool
+    pub liquidity_usd: f64,
+    /// Token supply in UI units
+    pub token_supply: f64,
+    /// Share of supply held by the largest non-pool holder
+    pub top1_share: f64,
+    /// Share of supply held by the ten largest non-pool holders
+    pub top10_share: f64,
+    pub mint_authority: bool,
+    pub freeze_authority: bool,
+    pub has_metadata: bool,
+    pub metadata_mutable: bool,
+    pub creator_launches: f64,
+    pub creator_rugs: f64,
+    pub creator_score: f64,
+    pub is_cpmm: bool,
+    /// Seconds until the pool opens for swaps (negative once open)
+    pub open_delay_secs: f64,
+}
+
+impl PoolFacts {
+    /// Every fact name and its type
+    pub const FIELDS: &'static [(&'static str, FactType)] = &[
+        ("liquidity_sol", FactType::Number),
+        ("liquidity_usd", FactType::Number),
+        ("token_supply", FactType::Number),
+        ("top1_share", FactType::Number),
+        ("top10_share", FactType::Number),
+        ("mint_authority", FactType::Bool),
+        ("freeze_authority", FactType::Bool),
+        ("has_metadata", FactType::Bool),
+        ("metadata_mutable", FactType::Bool),
+        ("creator_launches", FactType::Number),
+        ("creator_rugs", FactType::Number),
+        ("creator_score", FactType::Number),
+        ("is_cpmm", FactType::Bool),
+        ("open_delay_secs", FactType::Number),
+    ];
+
+    /// Type of a fact by name
+    pub fn type_of(name: &str) -> Option<FactType> {
+        Self::FIELDS
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, ty)| *ty)
+    }
+
+    /// Value of a fact by name
+    pub fn value(&self, name: &str) -> Option<FactValue> {
+        use FactValue::{Bool, Number};
+
+        Some(match name {
+            "liquidity_sol" => Number(self.liquidity_sol),
+            "liquidity_usd" => Number(self.liquidity_usd),
+            "token_supply" => Number(self.token_supply),
+            "top1_share" => Number(self.top1_share),
+            "top10_share" => Number(self.top10_share),
+            "mint_authority" => Bool(self.mint_authority),
+            "freeze_authority" => Bool(self.freeze_authority),
+            "has_metadata" => Bool(self.has_metadata),
+            "metadata_mutable" => Bool(self.metadata_mutable),
+            "creator_launches" => Number(self.creator_launches),
+            "creator_rugs" => Number(self.creator_rugs),
+            "creator_score" => Number(self.creator_score),
+            "is_cpmm" => Bool(self.is_cpmm),
+            "open_delay_secs" => Number(self.open_delay_secs),
+            _ => return None,
+        })
+    }
+
+    /// Gather facts for the pool being evaluated
+    pub async fn collect(ctx: &FilterContext, creator_store: &Arc<CreatorStore>) -> Result<Self> {
+        let keys = ctx.pool_keys().await?;
+        let mint = keys.token_mint();
+        let (sol_vault, token_vault) = if keys.base_mint == WSOL_MINT {
+            (keys.base_vault, keys.quote_vault)
+        } else {
+            (keys.quote_vault, keys.base_vault)
+        };
+
+        let rpc = &ctx.rpc_client;
+        let (sol_balance, mint_data, largest, metadata) = tokio::join!(
+            rpc.get_token_account_balance(&sol_vault),
+            rpc.get_account_data(&mint),
+            rpc.get_token_largest_accounts(&mint),
+            TokenMetadata::fetch(rpc, &mint),
+        );
+
+        let liquidity_sol = sol_balance
+            .context("Failed to fetch WSOL vault balance")?
+            .ui_amount
+            .unwrap_or(0.0);
+
+        let mint_data = mint_data.context("Failed to fetch mint account")?;
+        // Token-2022 mints carry extensions after the base layout
+        let mint_state = Mint::unpack_from_slice(
+            mint_data
+                .get(..Mint::LEN)
+                .context("Mint account too short")?,
+        )
+        .context("Failed to decode mint account")?;
+        let token_supply = mint_state.supply as f64 / 10f64.powi(mint_state.decimals as i32);
+
+        let token_vault_str = token_vault.to_string();
+        let holders: Vec<f64> = largest
+            .context("Failed to fetch largest token accounts")?
+            .into_iter()
+            .filter(|holder| holder.address != token_vault_str)
+            .map(|holder| holder.amount.ui_amount.unwrap_or(0.0))
+            .collect();
+        let share = |n: usize| {
+            if token_supply > 0.0 {
+                holders.iter().take(n).sum::<f64>() / token_supply
+            } else {
+                0.0
+            }
+        };
+
+        let history = creator_store.history(&ctx.event.creator);
+
+        Ok(Self {
+            liquidity_sol,
+            liquidity_usd: utils::estimate_usd_value_sol(liquidity_sol) * 2.0,
+            token_supply,
+            top1_share: share(1),
+            top10_share: share(10),
+            mint_authority: mint_state.mint_authority.is_some(),
+            freeze_authority: mint_state.freeze_authority.is_some(),
+            has_metadata: metadata.is_ok(),
+            metadata_mutable: metadata.as_ref().map(|m| m.is_mutable).unwrap_or(false),
+            creator_launches: history.as_ref().map(|h| h.launches.len()).unwrap_or(0) as f64,
+            creator_rugs: history.as_ref().map(|h| h.rug_count()).unwrap_or(0) as f64,
+            creator_score: history.as_ref().map(|h| h.score()).unwrap_or(0.5),
+            is_cpmm: keys.pool_type == crate::detector::PoolType::CPMM,
+            open_delay_secs: keys.open_time as f64 - chrono::Utc::now().timestamp() as f64,
+        })
+    }
+}
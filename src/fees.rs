@@ -0,0 +1,144 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// Approximate slot time used to convert the cache window to wall-clock time
+const SLOT_DURATION: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy)]
+struct CachedFee {
+    fee: u64,
+    fetched_at: Instant,
+}
+
+/// Priority fee estimator backed by `getRecentPrioritizationFees`
+///
+/// Estimates are cached per program and pool for `cache_slots` slots. Swaps
+/// query with their pool, since a hot launch bids well above the program as
+/// a whole; a background refresh keeps program-wide estimates warm as the
+/// fallback when a pool has no recent fees yet.
+pub struct PriorityFeeEstimator {
+    rpc_client: Arc<RpcClient>,
+    /// Fee used when no recent fees are available
+    base_fee: u64,
+    percentile: u8,
+    min_fee: u64,
+    max_fee: u64,
+    cache_window: Duration,
+    /// Keyed by program and pool; `None` holds the program-wide estimate
+    cache: Mutex<HashMap<(Pubkey, Option<Pubkey>), CachedFee>>,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(rpc_client: Arc<RpcClient>, config: &Config) -> Self {
+        Self {
+            rpc_client,
+            base_fee: config.priority_fee_micro_lamports,
+            percentile: config.priority_fee_percentile.min(100),
+            min_fee: config.priority_fee_min_micro_lamports,
+            max_fee: config.priority_fee_max_micro_lamports,
+            cache_window: SLOT_DURATION * config.priority_fee_cache_slots as u32,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Priority fee in micro-lamports per CU for a swap on `pool`
+    ///
+    /// Falls back to the program-wide estimate, then the base fee, when the
+    /// pool has no recent fees.
+    pub async fn estimate(&self, program_id: &Pubkey, pool: &Pubkey) -> u64 {
+        let key = (*program_id, Some(*pool));
+        if let Some(cached) = self.cached(&key, true) {
+            return cached;
+        }
+
+        match self.refresh(program_id, Some(pool)).await {
+            Some(fee) => fee,
+            None => self
+                .cached(&(*program_id, None), false)
+                .unwrap_or_else(|| self.clamp(self.base_fee)),
+        }
+    }
+
+    /// A cached estimate, only if still within the cache window when `fresh`
+    fn cached(&self, key: &(Pubkey, Option<Pubkey>), fresh: bool) -> Option<u64> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|c| !fresh || c.fetched_at.elapsed() < self.cache_window)
+            .map(|c| c.fee)
+    }
+
+    fn clamp(&self, fee: u64) -> u64 {
+        fee.clamp(self.min_fee, self.max_fee.max(self.min_fee))
+    }
+
+    /// Query recent fees for the program (and pool, if given) and update the cache
+    ///
+    /// Returns `None` if the query failed or saw no paid fees.
+    async fn refresh(&self, program_id: &Pubkey, pool: Option<&Pubkey>) -> Option<u64> {
+        let mut accounts = vec![*program_id];
+        accounts.extend(pool.copied());
+
+        let samples: Vec<u64> = match self.rpc_client.get_recent_prioritization_fees(&accounts).await {
+            Ok(fees) => fees
+                .iter()
+                .map(|f| f.prioritization_fee)
+                .filter(|fee| *fee > 0)
+                .collect(),
+            Err(e) => {
+                log::warn!("Failed to fetch recent prioritization fees: {}", e);
+                return None;
+            }
+        };
+        let fee = self.clamp(percentile(samples, self.percentile)?);
+
+        let mut cache = self.cache.lock().unwrap();
+        // Pools come and go; program-wide estimates are kept as fallbacks
+        cache.retain(|(_, pool), c| pool.is_none() || c.fetched_at.elapsed() < self.cache_window);
+        cache.insert(
+            (*program_id, pool.copied()),
+            CachedFee {
+                fee,
+                fetched_at: Instant::now(),
+            },
+        );
+        match pool {
+            Some(pool) => log::debug!(
+                "Priority fee for {} on {}: {} micro-lamports/CU",
+                pool,
+                program_id,
+                fee
+            ),
+            None => log::debug!("Priority fee for {}: {} micro-lamports/CU", program_id, fee),
+        }
+
+        Some(fee)
+    }
+
+    /// Keep program-wide estimates warm for the given programs
+    pub async fn run_refresh(self: Arc<Self>, program_ids: Vec<Pubkey>) {
+        let interval = self.cache_window.max(SLOT_DURATION) / 2;
+        loop {
+            for program_id in &program_ids {
+                self.refresh(program_id, None).await;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Nearest-rank percentile of the samples (`pct` above 100 reads as 100)
+pub fn percentile(mut samples: Vec<u64>, pct: u8) -> Option<u64> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    samples.sort_unstable();
+    let rank = ((pct.min(100) as usize * samples.len() + 99) / 100).max(1);
+    Some(samples[rank - 1])
+}
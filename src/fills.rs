@@ -0,0 +1,365 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use tokio::time::{sleep, Duration};
+
+use crate::pool::WSOL_MINT;
+
+/// Tries at fetching a just-confirmed transaction, which RPC nodes may not serve yet
+const FETCH_ATTEMPTS: u32 = 5;
+
+const FETCH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// What a landed swap actually did to the wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub signature: String,
+    pub slot: u64,
+    pub mint: String,
+    /// Raw token amount received (negative for a sell)
+    pub token_delta: i128,
+    pub decimals: u8,
+    /// SOL spent including network fee and tip, excluding rent deposits (negative for a sell)
+    pub sol_spent_lamports: i64,
+    pub fee_lamports: u64,
+    pub tip_lamports: u64,
+    /// Lamports locked as rent in token accounts the swap created
+    pub rent_lamports: u64,
+    /// SOL per whole token, all costs included
+    pub effective_price: f64,
+    /// Tokens the pre-trade quote expected
+    pub quoted_tokens: Option<u64>,
+    /// Shortfall versus the quote in basis points (negative = better than quoted)
+    pub slippage_bps: Option<i64>,
+}
+
+impl Fill {
+    /// Tokens received, or 0 if the fill was a sell
+    pub fn tokens_received(&self) -> u64 {
+        self.token_delta.max(0) as u64
+    }
+}
+
+/// Fetch a confirmed transaction and parse the wallet's fill in `mint`
+pub async fn fetch_fill(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    tip_lamports: u64,
+    quoted_tokens: Option<u64>,
+) -> Result<Fill> {
+    let mut attempt = 1;
+    let tx = loop {
+        match rpc_client
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+        {
+            Ok(tx) => break tx,
+            Err(e) if attempt < FETCH_ATTEMPTS => {
+                log::debug!("Transaction {} not available yet ({}), retrying", signature, e);
+                attempt += 1;
+                sleep(FETCH_RETRY_DELAY).await;
+            }
+            Err(e) => return Err(e).context("Failed to fetch transaction"),
+        }
+    };
+
+    parse_fill(&tx, wallet, mint, tip_lamports, quoted_tokens)
+}
+
+/// `(account index, raw amount, decimals)` of each `mint` account owned by `owner`
+fn owned_balances(
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    owner: &str,
+    mint: &str,
+) -> Vec<(u8, u64, u8)> {
+    let OptionSerializer::Some(balances) = balances else {
+        return vec![];
+    };
+
+    balances
+        .iter()
+        .filter(|b| b.mint == mint && matches!(&b.owner, OptionSerializer::Some(o) if o == owner))
+        .filter_map(|b| {
+            let amount = b.ui_token_amount.amount.parse().ok()?;
+            Some((b.account_index, amount, b.ui_token_amount.decimals))
+        })
+        .collect()
+}
+
+fn total(balances: &[(u8, u64, u8)]) -> i128 {
+    balances.iter().map(|(_, amount, _)| *amount as i128).sum()
+}
+
+/// Parse the wallet's fill in `mint` from pre/post balances in the transaction meta
+///
+/// The wallet must be the fee payer. `tip_lamports` is the Jito tip the
+/// transaction paid, which is part of the wallet's lamport change.
+pub fn parse_fill(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    tip_lamports: u64,
+    quoted_tokens: Option<u64>,
+) -> Result<Fill> {
+    let meta = tx
+        .transaction
+        .meta
+        .as_ref()
+        .context("Transaction has no status meta")?;
+    if let Some(ref err) = meta.err {
+        anyhow::bail!("Transaction failed: {}", err);
+    }
+
+    let decoded = tx
+        .transaction
+        .transaction
+        .decode()
+        .context("Failed to decode transaction")?;
+    let signature = decoded.signatures[0].to_string();
+    if decoded.message.static_account_keys().first() != Some(wallet) {
+        anyhow::bail!("Wallet is not the fee payer of {}", signature);
+    }
+
+    let owner = wallet.to_string();
+    let mint_str = mint.to_string();
+    let wsol = WSOL_MINT.to_string();
+
+    let pre_tokens = owned_balances(&meta.pre_token_balances, &owner, &mint_str);
+    let post_tokens = owned_balances(&meta.post_token_balances, &owner, &mint_str);
+    let pre_wsol = owned_balances(&meta.pre_token_balances, &owner, &wsol);
+    let post_wsol = owned_balances(&meta.post_token_balances, &owner, &wsol);
+
+    let token_delta = total(&post_tokens) - total(&pre_tokens);
+    let decimals = post_tokens
+        .iter()
+        .chain(&pre_tokens)
+        .map(|(_, _, d)| *d)
+        .next()
+        .context("Transaction did not touch the wallet's token account")?;
+
+    // SOL spent = payer lamports out + WSOL tokens out, minus rent parked in new token accounts
+    let lamports_out = meta.pre_balances[0] as i64 - meta.post_balances[0] as i64;
+    let wsol_out = total(&pre_wsol) - total(&post_wsol);
+
+    let existed = |index: &u8| pre_tokens.iter().chain(&pre_wsol).any(|(i, _, _)| i == index);
+    let rent_lamports: u64 = post_tokens
+        .iter()
+        .chain(&post_wsol)
+        .map(|(i, _, _)| i)
+        .filter(|i| !existed(i))
+        .filter_map(|i| meta.post_balances.get(*i as usize))
+        .sum();
+
+    let sol_spent_lamports = lamports_out + wsol_out as i64 - rent_lamports as i64;
+
+    let whole_tokens = token_delta.unsigned_abs() as f64 / 10f64.powi(decimals as i32);
+    let effective_price = if whole_tokens > 0.0 {
+        (sol_spent_lamports.unsigned_abs() as f64 / 1_000_000_000.0) / whole_tokens
+    } else {
+        0.0
+    };
+
+    let slippage_bps = quoted_tokens.filter(|q| *q > 0).map(|quoted| {
+        ((quoted as i128 - token_delta) * 10_000 / quoted as i128) as i64
+    });
+
+    Ok(Fill {
+        signature,
+        slot: tx.slot,
+        mint: mint_str,
+        token_delta,
+        decimals,
+        sol_spent_lamports,
+        fee_lamports: meta.fee,
+        tip_lamports,
+        rent_lamports,
+        effective_price,
+        quoted_tokens,
+        slippage_bps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use serde_json::{json, Value};
+    use solana_sdk::{
+        hash::Hash, signature::Keypair, signer::Signer, system_transaction,
+        transaction::VersionedTransaction,
+    };
+
+    const FEE: u64 = 5_000;
+    const RENT: u64 = 2_039_280;
+    const SOL: u64 = 1_000_000_000;
+
+    /// Account indexes in the meta: 0 wallet, 1 token account, 2 WSOL account
+    fn token_balance(index: u8, mint: &Pubkey, owner: &Pubkey, amount: u64, decimals: u8) -> Value {
+        json!({
+            "accountIndex": index,
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "programId": spl_token::id().to_string(),
+            "uiTokenAmount": {
+                "uiAmount": null,
+                "decimals": decimals,
+                "amount": amount.to_string(),
+                "uiAmountString": "",
+            },
+        })
+    }
+
+    fn confirmed(
+        wallet: &Keypair,
+        pre_balances: [u64; 3],
+        post_balances: [u64; 3],
+        pre_tokens: Vec<Value>,
+        post_tokens: Vec<Value>,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let transfer = system_transaction::transfer(wallet, &Pubkey::new_unique(), 1, Hash::default());
+        let wire = bincode::serialize(&VersionedTransaction::from(transfer)).unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(wire);
+
+        serde_json::from_value(json!({
+            "slot": 42,
+            "transaction": [encoded, "base64"],
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": FEE,
+                "preBalances": pre_balances,
+                "postBalances": post_balances,
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": pre_tokens,
+                "postTokenBalances": post_tokens,
+                "rewards": [],
+            },
+            "blockTime": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn buy_excludes_rent_of_new_token_account() {
+        let wallet = Keypair::new();
+        let owner = wallet.pubkey();
+        let mint = Pubkey::new_unique();
+        let start = 10 * SOL;
+        let tx = confirmed(
+            &wallet,
+            [start, 0, 0],
+            [start - SOL - RENT - FEE, RENT, 0],
+            vec![],
+            vec![token_balance(1, &mint, &owner, 1_000_000, 6)],
+        );
+
+        let fill = parse_fill(&tx, &owner, &mint, 0, Some(1_010_000)).unwrap();
+        assert_eq!(fill.token_delta, 1_000_000);
+        assert_eq!(fill.tokens_received(), 1_000_000);
+        assert_eq!(fill.rent_lamports, RENT);
+        assert_eq!(fill.sol_spent_lamports, (SOL + FEE) as i64);
+        assert_eq!(fill.slippage_bps, Some(99));
+        assert_eq!(fill.slot, 42);
+    }
+
+    #[test]
+    fn buy_from_existing_wsol_counts_wrapped_sol_spent() {
+        let wallet = Keypair::new();
+        let owner = wallet.pubkey();
+        let mint = Pubkey::new_unique();
+        let start = 10 * SOL;
+        let tx = confirmed(
+            &wallet,
+            [start, RENT, RENT + SOL / 2],
+            [start - FEE, RENT, RENT],
+            vec![
+                token_balance(1, &mint, &owner, 0, 6),
+                token_balance(2, &WSOL_MINT, &owner, SOL / 2, 9),
+            ],
+            vec![
+                token_balance(1, &mint, &owner, 500, 6),
+                token_balance(2, &WSOL_MINT, &owner, 0, 9),
+            ],
+        );
+
+        let fill = parse_fill(&tx, &owner, &mint, 0, None).unwrap();
+        assert_eq!(fill.token_delta, 500);
+        assert_eq!(fill.rent_lamports, 0);
+        assert_eq!(fill.sol_spent_lamports, (SOL / 2 + FEE) as i64);
+    }
+
+    #[test]
+    fn wsol_wrapped_and_closed_in_the_swap_is_not_rent() {
+        let wallet = Keypair::new();
+        let owner = wallet.pubkey();
+        let mint = Pubkey::new_unique();
+        let start = 10 * SOL;
+        let tip = 10_000;
+        // The temporary WSOL account is created and closed in the same transaction,
+        // so it has no token balances and its rent comes back to the wallet
+        let tx = confirmed(
+            &wallet,
+            [start, RENT, 0],
+            [start - SOL - FEE - tip, RENT, 0],
+            vec![token_balance(1, &mint, &owner, 0, 6)],
+            vec![token_balance(1, &mint, &owner, 2_000, 6)],
+        );
+
+        let fill = parse_fill(&tx, &owner, &mint, tip, None).unwrap();
+        assert_eq!(fill.rent_lamports, 0);
+        assert_eq!(fill.tip_lamports, tip);
+        assert_eq!(fill.sol_spent_lamports, (SOL + FEE + tip) as i64);
+    }
+
+    #[test]
+    fn sell_has_negative_deltas() {
+        let wallet = Keypair::new();
+        let owner = wallet.pubkey();
+        let mint = Pubkey::new_unique();
+        let start = 10 * SOL;
+        let proceeds = 900_000_000;
+        let tx = confirmed(
+            &wallet,
+            [start, RENT, 0],
+            [start + proceeds - FEE, RENT, 0],
+            vec![token_balance(1, &mint, &owner, 1_000_000, 6)],
+            vec![token_balance(1, &mint, &owner, 0, 6)],
+        );
+
+        let fill = parse_fill(&tx, &owner, &mint, 0, None).unwrap();
+        assert_eq!(fill.token_delta, -1_000_000);
+        assert_eq!(fill.tokens_received(), 0);
+        assert_eq!(fill.sol_spent_lamports, -((proceeds - FEE) as i64));
+        assert!((fill.effective_price - 0.899995).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_transactions_paid_by_someone_else() {
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let tx = confirmed(
+            &payer,
+            [SOL, 0, 0],
+            [SOL - FEE, 0, 0],
+            vec![],
+            vec![token_balance(1, &mint, &payer.pubkey(), 1, 6)],
+        );
+
+        assert!(parse_fill(&tx, &Pubkey::new_unique(), &mint, 0, None).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::detector::PoolCreationEvent;
+use crate::pool::PoolKeys;
+
+/// How expensive a filter is to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostTier {
+    /// Local checks with no network access; run first, in order
+    Cheap,
+    /// RPC-heavy checks; run concurrently once all cheap filters pass
+    Expensive,
+}
+
+/// Outcome of a single filter
+#[derive(Debug, Clone)]
+pub struct FilterVerdict {
+    /// `false` is a hard reject and stops the pipeline
+    pub pass: bool,
+    /// Human-readable notes explaining the verdict
+    pub reasons: Vec<String>,
+    /// Filter-specific quality score in [0, 1]; `None` when the filter could
+    /// not judge the pool and should not count towards its score
+    pub score: Option<f64>,
+}
+
+impl FilterVerdict {
+    /// Pass with a full score
+    pub fn pass() -> Self {
+        Self {
+            pass: true,
+            reasons: vec![],
+            score: Some(1.0),
+        }
+    }
+
+    /// Pass with a score and an explanatory note
+    pub fn pass_with(score: f64, reason: impl Into<String>) -> Self {
+        Self {
+            pass: true,
+            reasons: vec![reason.into()],
+            score: Some(score),
+        }
+    }
+
+    /// Pass without judging the pool, leaving it out of the weighted score
+    pub fn skip(reason: impl Into<String>) -> Self {
+        Self {
+            pass: true,
+            reasons: vec![reason.into()],
+            score: None,
+        }
+    }
+
+    /// Hard reject
+    pub fn reject(reason: impl Into<String>) -> Self {
+        Self {
+            pass: false,
+            reasons: vec![reason.into()],
+            score: Some(0.0),
+        }
+    }
+}
+
+/// Shared inputs for filters evaluating one pool
+pub struct FilterContext {
+    pub event: PoolCreationEvent,
+    pub rpc_client: Arc<RpcClient>,
+    pool_keys: OnceCell<PoolKeys>,
+}
+
+impl FilterContext {
+    pub fn new(event: PoolCreationEvent, rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            event,
+            rpc_client,
+            pool_keys: OnceCell::new(),
+        }
+    }
+
+    /// Decoded pool state, fetched once and shared between filters
+    pub async fn pool_keys(&self) -> Result<&PoolKeys> {
+        self.pool_keys
+            .get_or_try_init(|| {
+                PoolKeys::fetch(&self.rpc_client, &self.event.pool, self.event.pool_type)
+            })
+            .await
+    }
+}
+
+/// A check applied to newly detected pools
+#[async_trait]
+pub trait PoolFilter: Send + Sync {
+    /// Short identifier used in logs and reports
+    fn name(&self) -> &str;
+
+    fn cost(&self) -> CostTier;
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict>;
+}
+
+/// Verdict and timing of one filter run
+#[derive(Debug, Clone)]
+pub struct FilterResult {
+    pub name: String,
+    pub verdict: FilterVerdict,
+    pub latency: Duration,
+}
+
+/// Result of running the pipeline on a pool
+#[derive(Debug, Clone)]
+pub struct EvaluationReport {
+    pub passed: bool,
+    pub results: Vec<FilterResult>,
+    pub total_latency: Duration,
+}
+
+impl EvaluationReport {
+    /// The filter that rejected the pool, if any
+    pub fn rejected_by(&self) -> Option<&FilterResult> {
+        self.results.iter().find(|r| !r.verdict.pass)
+    }
+}
+
+impl fmt::Display for EvaluationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {:?}",
+            if self.passed { "PASS" } else { "REJECT" },
+            self.total_latency
+        )?;
+        for result in &self.results {
+            let score = match result.verdict.score {
+                Some(score) => format!("{:.2}", score),
+                None => "-".to_string(),
+            };
+            write!(
+                f,
+                "\n  {:<12} {} score={} {:?}",
+                result.name,
+                if result.verdict.pass { "pass" } else { "FAIL" },
+                score,
+                result.latency
+            )?;
+            for reason in &result.verdict.reasons {
+                write!(f, "\n    - {}", reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Ordered set of filters applied to each new pool
+#[derive(Default)]
+pub struct FilterPipeline {
+    filters: Vec<Box<dyn PoolFilter>>,
+    pass_on_error: bool,
+}
+
+impl FilterPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a filter to the pipeline
+    pub fn with(mut self, filter: impl PoolFilter + 'static) -> Self {
+        self.add(Box::new(filter));
+        self
+    }
+
+    /// Let a filter that errors pass instead of rejecting the pool
+    pub fn pass_on_error(mut self, pass: bool) -> Self {
+        self.pass_on_error = pass;
+        self
+    }
+
+    pub fn add(&mut self, filter: Box<dyn PoolFilter>) {
+        self.filters.push(filter);
+    }
+
+    /// Run all filters against a pool
+    ///
+    /// Cheap filters run sequentially in insertion order, then expensive ones
+    /// concurrently. The first hard reject stops the run. A filter that errors
+    /// is logged and rejects the pool, unless `pass_on_error` is set, in which
+    /// case it passes unscored with a note in the report.
+    pub async fn run(&self, ctx: &FilterContext) -> EvaluationReport {
+        let start = Instant::now();
+        let mut results = Vec::with_capacity(self.filters.len());
+
+        for filter in self.filters.iter().filter(|f| f.cost() == CostTier::Cheap) {
+            let result = run_filter(filter.as_ref(), ctx, self.pass_on_error).await;
+            let rejected = !result.verdict.pass;
+            results.push(result);
+            if rejected {
+                return EvaluationReport {
+                    passed: false,
+                    results,
+                    total_latency: start.elapsed(),
+                };
+            }
+        }
+
+        let mut pending: FuturesUnordered<_> = self
+            .filters
+            .iter()
+            .filter(|f| f.cost() == CostTier::Expensive)
+            .map(|f| run_filter(f.as_ref(), ctx, self.pass_on_error))
+            .collect();
+
+        let mut passed = true;
+        while let Some(result) = pending.next().await {
+            let rejected = !result.verdict.pass;
+            results.push(result);
+            if rejected {
+                // Dropping the remaining futures cancels their in-flight requests
+                passed = false;
+                break;
+            }
+        }
+
+        EvaluationReport {
+            passed,
+            results,
+            total_latency: start.elapsed(),
+        }
+    }
+}
+
+async fn run_filter(filter: &dyn PoolFilter, ctx: &FilterContext, pass_on_error: bool) -> FilterResult {
+    let start = Instant::now();
+    let verdict = match filter.evaluate(ctx).await {
+        Ok(verdict) => verdict,
+        Err(e) => {
+            log::warn!("Filter {} failed for {}: {}", filter.name(), ctx.event.pool, e);
+            let reason = format!("check failed: {}", e);
+            if pass_on_error {
+                FilterVerdict::skip(reason)
+            } else {
+                FilterVerdict::reject(reason)
+            }
+        }
+    };
+
+    FilterResult {
+        name: filter.name().to_string(),
+        verdict,
+        latency: start.elapsed(),
+    }
+}
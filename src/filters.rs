@@ -0,0 +1,333 @@
+//! Built-in pool filters
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use solana_sdk::program_option::COption;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token::state::Mint;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::filter::{CostTier, FilterContext, FilterVerdict, PoolFilter};
+use crate::funding::trace_funding;
+use crate::metadata::{MetadataRules, TokenMetadata};
+use crate::pool::WSOL_MINT;
+use crate::reputation::CreatorStore;
+use crate::utils;
+
+/// Rejects pools from blacklisted creators
+pub struct BlacklistFilter {
+    creators: Arc<HashSet<Pubkey>>,
+}
+
+impl BlacklistFilter {
+    pub fn new(creators: Arc<HashSet<Pubkey>>) -> Self {
+        Self { creators }
+    }
+}
+
+#[async_trait]
+impl PoolFilter for BlacklistFilter {
+    fn name(&self) -> &str {
+        "blacklist"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Cheap
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        if self.creators.contains(&ctx.event.creator) {
+            return Ok(FilterVerdict::reject(format!(
+                "creator {} is blacklisted",
+                ctx.event.creator
+            )));
+        }
+
+        Ok(FilterVerdict::pass())
+    }
+}
+
+/// Rejects creators with a history of rugged launches
+pub struct ReputationFilter {
+    store: Arc<CreatorStore>,
+    max_rugs: usize,
+    min_score: f64,
+}
+
+impl ReputationFilter {
+    pub fn new(store: Arc<CreatorStore>, config: &Config) -> Self {
+        Self {
+            store,
+            max_rugs: config.creator_max_rugs,
+            min_score: config.creator_min_reputation,
+        }
+    }
+}
+
+#[async_trait]
+impl PoolFilter for ReputationFilter {
+    fn name(&self) -> &str {
+        "reputation"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Cheap
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        let creator = &ctx.event.creator;
+        if let Some(reason) = self.store.check_creator(creator, self.max_rugs, self.min_score) {
+            return Ok(FilterVerdict::reject(reason));
+        }
+
+        Ok(match self.store.history(creator) {
+            Some(history) => FilterVerdict::pass_with(
+                history.score(),
+                format!(
+                    "{} launches seen, {} rugged",
+                    history.launches.len(),
+                    history.rug_count()
+                ),
+            ),
+            None => FilterVerdict::skip("no history for creator"),
+        })
+    }
+}
+
+/// Traces the creator's funding chain and rejects blacklisted or rugging funders
+pub struct FundingFilter {
+    store: Arc<CreatorStore>,
+    blacklisted_creators: Arc<HashSet<Pubkey>>,
+    blacklisted_funders: Arc<HashSet<Pubkey>>,
+    max_hops: usize,
+    max_signatures: usize,
+    max_rugs: usize,
+    min_score: f64,
+}
+
+impl FundingFilter {
+    pub fn new(
+        store: Arc<CreatorStore>,
+        blacklisted_creators: Arc<HashSet<Pubkey>>,
+        blacklisted_funders: Arc<HashSet<Pubkey>>,
+        config: &Config,
+    ) -> Self {
+        Self {
+            store,
+            blacklisted_creators,
+            blacklisted_funders,
+            max_hops: config.funding_trace_hops,
+            max_signatures: config.funding_trace_max_signatures,
+            max_rugs: config.creator_max_rugs,
+            min_score: config.creator_min_reputation,
+        }
+    }
+}
+
+#[async_trait]
+impl PoolFilter for FundingFilter {
+    fn name(&self) -> &str {
+        "funding"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Expensive
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        if self.max_hops == 0 {
+            return Ok(FilterVerdict::skip("funding trace disabled"));
+        }
+
+        let chain = trace_funding(
+            &ctx.rpc_client,
+            &ctx.event.creator,
+            self.max_hops,
+            self.max_signatures,
+        )
+        .await?;
+        let chain_note = format!("funding chain: {}", chain);
+
+        for funder in chain.funders() {
+            if self.blacklisted_funders.contains(funder) || self.blacklisted_creators.contains(funder) {
+                let mut verdict =
+                    FilterVerdict::reject(format!("funded by blacklisted wallet {}", funder));
+                verdict.reasons.push(chain_note);
+                return Ok(verdict);
+            }
+
+            if let Some(reason) = self.store.check_creator(funder, self.max_rugs, self.min_score) {
+                let mut verdict = FilterVerdict::reject(format!("funder {}: {}", funder, reason));
+                verdict.reasons.push(chain_note);
+                return Ok(verdict);
+            }
+        }
+
+        Ok(FilterVerdict::pass_with(1.0, chain_note))
+    }
+}
+
+/// Rejects pools whose WSOL side is outside the configured liquidity range
+pub struct LiquidityFilter {
+    min_usd: f64,
+    max_usd: Option<f64>,
+}
+
+impl LiquidityFilter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            min_usd: config.min_liquidity_usd,
+            max_usd: config.max_liquidity_usd,
+        }
+    }
+}
+
+#[async_trait]
+impl PoolFilter for LiquidityFilter {
+    fn name(&self) -> &str {
+        "liquidity"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Expensive
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        let keys = ctx.pool_keys().await?;
+        let sol_vault = if keys.base_mint == WSOL_MINT {
+            keys.base_vault
+        } else {
+            keys.quote_vault
+        };
+
+        let liquidity_sol = ctx
+            .rpc_client
+            .get_token_account_balance(&sol_vault)
+            .await
+            .context("Failed to fetch WSOL vault balance")?
+            .ui_amount
+            .unwrap_or(0.0);
+        // Both sides of the pool hold the same value
+        let liquidity_usd = utils::estimate_usd_value_sol(liquidity_sol) * 2.0;
+        let note = format!("liquidity {:.2} SOL (~${:.0})", liquidity_sol, liquidity_usd);
+
+        if liquidity_usd < self.min_usd {
+            return Ok(FilterVerdict::reject(format!(
+                "{} is below the ${:.0} minimum",
+                note, self.min_usd
+            )));
+        }
+        if let Some(max_usd) = self.max_usd {
+            if liquidity_usd > max_usd {
+                return Ok(FilterVerdict::reject(format!(
+                    "{} is above the ${:.0} maximum",
+                    note, max_usd
+                )));
+            }
+        }
+
+        // Deeper pools score higher, saturating at ten times the minimum
+        let score = if self.min_usd > 0.0 {
+            (liquidity_usd / (self.min_usd * 10.0)).min(1.0)
+        } else {
+            1.0
+        };
+        Ok(FilterVerdict::pass_with(score, note))
+    }
+}
+
+/// Rejects tokens whose mint or freeze authority is still set
+pub struct RugFilter;
+
+#[async_trait]
+impl PoolFilter for RugFilter {
+    fn name(&self) -> &str {
+        "rug"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Expensive
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        let mint = ctx.pool_keys().await?.token_mint();
+        let data = ctx
+            .rpc_client
+            .get_account_data(&mint)
+            .await
+            .context("Failed to fetch mint account")?;
+        // Token-2022 mints carry extensions after the base layout
+        let state = Mint::unpack_from_slice(data.get(..Mint::LEN).context("Mint account too short")?)
+            .context("Failed to decode mint account")?;
+
+        if let COption::Some(authority) = state.mint_authority {
+            return Ok(FilterVerdict::reject(format!(
+                "mint authority {} can inflate supply",
+                authority
+            )));
+        }
+        if let COption::Some(authority) = state.freeze_authority {
+            return Ok(FilterVerdict::reject(format!(
+                "freeze authority {} can freeze holders",
+                authority
+            )));
+        }
+
+        Ok(FilterVerdict::pass_with(1.0, "mint and freeze authority revoked"))
+    }
+}
+
+/// Decodes the token's Metaplex metadata and applies the name/symbol rules
+pub struct MetadataFilter {
+    rules: MetadataRules,
+}
+
+impl MetadataFilter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            rules: MetadataRules::from_config(config),
+        }
+    }
+}
+
+#[async_trait]
+impl PoolFilter for MetadataFilter {
+    fn name(&self) -> &str {
+        "metadata"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Expensive
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        if self.rules.is_empty() {
+            return Ok(FilterVerdict::skip("no metadata rules configured"));
+        }
+
+        let mint = ctx.pool_keys().await?.token_mint();
+        let Some(metadata) = TokenMetadata::fetch_optional(&ctx.rpc_client, &mint).await? else {
+            return Ok(FilterVerdict::skip("token has no metadata account yet"));
+        };
+        let summary = format!(
+            "name={:?} symbol={:?} uri={} update_authority={} mutable={} creators={}",
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+            metadata.update_authority,
+            metadata.is_mutable,
+            metadata.creators.len()
+        );
+
+        if let Some(reason) = self.rules.check(&metadata, &ctx.event.creator) {
+            let mut verdict = FilterVerdict::reject(reason);
+            verdict.reasons.push(summary);
+            return Ok(verdict);
+        }
+
+        Ok(FilterVerdict::pass_with(1.0, summary))
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, system_program};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction, UiTransactionEncoding,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// Oldest transactions inspected per hop when looking for the funding transfer
+const MAX_TRANSACTIONS_PER_HOP: usize = 25;
+
+/// Transactions fetched at once per hop
+const FETCH_CONCURRENCY: usize = 8;
+
+/// One step in a funding chain: `funder` sent `lamports` to `recipient`
+#[derive(Debug, Clone)]
+pub struct FundingHop {
+    pub funder: Pubkey,
+    pub recipient: Pubkey,
+    pub lamports: u64,
+    pub signature: String,
+}
+
+/// Chain of SOL transfers leading to a wallet, nearest hop first
+#[derive(Debug, Clone, Default)]
+pub struct FundingChain {
+    pub hops: Vec<FundingHop>,
+    /// Set when tracing stopped at a wallet with too much history to walk (e.g. an exchange)
+    pub stopped_at_busy_wallet: Option<Pubkey>,
+}
+
+impl FundingChain {
+    /// Every wallet that funded the chain, nearest first
+    pub fn funders(&self) -> impl Iterator<Item = &Pubkey> {
+        self.hops.iter().map(|hop| &hop.funder)
+    }
+}
+
+impl fmt::Display for FundingChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.hops.first() else {
+            return write!(f, "(no funding found)");
+        };
+
+        write!(f, "{}", first.recipient)?;
+        for hop in &self.hops {
+            write!(
+                f,
+                " <- {} ({:.4} SOL)",
+                hop.funder,
+                crate::utils::lamports_to_sol(hop.lamports)
+            )?;
+        }
+        if let Some(busy) = self.stopped_at_busy_wallet {
+            write!(f, " [stopped at busy wallet {}]", busy)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Walk back up to `max_hops` SOL transfers into `wallet`
+///
+/// At each hop the earliest incoming system transfer, top-level or inner, is
+/// taken as the funding source. Only the wallet's `MAX_TRANSACTIONS_PER_HOP`
+/// oldest successful transactions are fetched, `FETCH_CONCURRENCY` at a time.
+/// Wallets with `max_signatures` or more transactions are treated as shared
+/// infrastructure (exchanges, bridges) and end the trace.
+pub async fn trace_funding(
+    rpc_client: &RpcClient,
+    wallet: &Pubkey,
+    max_hops: usize,
+    max_signatures: usize,
+) -> Result<FundingChain> {
+    let mut chain = FundingChain::default();
+    let mut current = *wallet;
+
+    for _ in 0..max_hops {
+        let signatures = rpc_client
+            .get_signatures_for_address_with_config(
+                &current,
+                GetConfirmedSignaturesForAddress2Config {
+                    limit: Some(max_signatures),
+                    ..Default::default()
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to fetch signatures for {}", current))?;
+
+        if signatures.len() >= max_signatures {
+            chain.stopped_at_busy_wallet = Some(current);
+            break;
+        }
+
+        // Signatures are returned newest first; funding is usually the oldest transfer.
+        // Fetches run concurrently but complete in order, so the first hit is the oldest.
+        let recipient = current;
+        let mut lookups = stream::iter(
+            signatures
+                .iter()
+                .rev()
+                .filter(|s| s.err.is_none())
+                .take(MAX_TRANSACTIONS_PER_HOP),
+        )
+        .map(|sig_info| find_incoming_transfer(rpc_client, &sig_info.signature, &recipient))
+        .buffered(FETCH_CONCURRENCY);
+
+        let mut hop = None;
+        while let Some(found) = lookups.next().await {
+            // Leaving the loop drops the stream, cancelling the fetches still in flight
+            if let Some(found) = found? {
+                hop = Some(found);
+                break;
+            }
+        }
+
+        let Some(hop) = hop else {
+            break;
+        };
+
+        // Stop on cycles (wallets funding each other back and forth)
+        if hop.funder == *wallet || chain.funders().any(|f| *f == hop.funder) {
+            break;
+        }
+
+        current = hop.funder;
+        chain.hops.push(hop);
+    }
+
+    Ok(chain)
+}
+
+/// Find a system transfer into `recipient` within a transaction, including
+/// transfers made by inner (CPI) instructions
+async fn find_incoming_transfer(
+    rpc_client: &RpcClient,
+    signature: &str,
+    recipient: &Pubkey,
+) -> Result<Option<FundingHop>> {
+    let sig = Signature::from_str(signature).context("Invalid signature")?;
+    let tx = rpc_client
+        .get_transaction_with_config(
+            &sig,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::JsonParsed),
+                max_supported_transaction_version: Some(0),
+                ..Default::default()
+            },
+        )
+        .await
+        .with_context(|| format!("Failed to fetch transaction {}", signature))?;
+
+    let EncodedTransaction::Json(ui_tx) = tx.transaction.transaction else {
+        return Ok(None);
+    };
+    let UiMessage::Parsed(message) = ui_tx.message else {
+        return Ok(None);
+    };
+
+    let inner = match tx.transaction.meta.map(|meta| meta.inner_instructions) {
+        Some(OptionSerializer::Some(inner)) => inner,
+        _ => vec![],
+    };
+    let instructions = message
+        .instructions
+        .iter()
+        .chain(inner.iter().flat_map(|set| set.instructions.iter()));
+
+    let recipient_str = recipient.to_string();
+    let system_program_str = system_program::id().to_string();
+
+    for ix in instructions {
+        let UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed)) = ix else {
+            continue;
+        };
+        if parsed.program_id != system_program_str {
+            continue;
+        }
+
+        let kind = parsed.parsed.get("type").and_then(|t| t.as_str());
+        if !matches!(kind, Some("transfer") | Some("transferWithSeed")) {
+            continue;
+        }
+
+        let info = &parsed.parsed["info"];
+        if info["destination"].as_str() != Some(recipient_str.as_str()) {
+            continue;
+        }
+
+        let Some(source) = info["source"].as_str().and_then(|s| Pubkey::from_str(s).ok()) else {
+            continue;
+        };
+
+        return Ok(Some(FundingHop {
+            funder: source,
+            recipient: *recipient,
+            lamports: info["lamports"].as_u64().unwrap_or(0),
+            signature: signature.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
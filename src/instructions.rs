@@ -2,11 +2,11 @@ use anyhow::{Context, Result};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    system_program,
 };
 use std::str::FromStr;
 
 use crate::config::{RAYDIUM_AMM_V4_PROGRAM_ID, RAYDIUM_CPMM_PROGRAM_ID};
+use crate::pool::{self, MarketKeys, PoolKeys, SwapAccounts};
 
 /// Raydium instruction discriminators
 /// 
@@ -21,63 +21,71 @@ pub mod discriminators {
     /// Initialize instruction (AMM v4)
     pub const INITIALIZE: [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
     
-    /// Swap instruction (AMM v4)
-    pub const SWAP: [u8; 8] = [225, 226, 218, 232, 240, 105, 206, 129];
+    /// SwapBaseIn instruction tag (AMM v4, single-byte native instruction)
+    pub const SWAP_BASE_IN: u8 = 9;
     
     /// CPMM Initialize
     pub const CPMM_INITIALIZE: [u8; 8] = [0; 8]; // Placeholder - verify with IDL
     
-    /// CPMM Swap
-    pub const CPMM_SWAP: [u8; 8] = [0; 8]; // Placeholder - verify with IDL
+    /// CPMM SwapBaseInput (Anchor `global:swap_base_input`)
+    pub const CPMM_SWAP_BASE_INPUT: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+
+    /// Withdraw instruction tag (AMM v4, single-byte native instruction)
+    pub const WITHDRAW: u8 = 4;
+
+    /// CPMM Withdraw (Anchor `global:withdraw`)
+    pub const CPMM_WITHDRAW: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
 }
 
-/// Build a Raydium AMM v4 swap instruction
-/// 
-/// This is a simplified version. In production, you should:
-/// 1. Use the Raydium SDK or Anchor IDL
-/// 2. Properly derive all required accounts (pool, amm, authority, etc.)
-/// 3. Calculate amount_in and min_amount_out with slippage
-pub fn build_raydium_swap_instruction(
-    user: &Pubkey,
-    pool: &Pubkey,
-    amm: &Pubkey,
-    amm_authority: &Pubkey,
-    amm_open_orders: &Pubkey,
-    amm_target_orders: &Pubkey,
-    pool_coin_token_account: &Pubkey,
-    pool_pc_token_account: &Pubkey,
-    serum_program_id: &Pubkey,
-    serum_market: &Pubkey,
-    user_source_token_account: &Pubkey,
-    user_dest_token_account: &Pubkey,
-    user_source_owner: &Pubkey,
+/// Build a Raydium AMM v4 `SwapBaseIn` instruction
+///
+/// Swaps `amount_in` from `user_source` into `user_dest`; the direction follows
+/// from which mint the source account holds.
+pub fn build_amm_v4_swap_instruction(
+    keys: &PoolKeys,
+    market: &MarketKeys,
+    user_source: &Pubkey,
+    user_dest: &Pubkey,
+    user_owner: &Pubkey,
     amount_in: u64,
     min_amount_out: u64,
 ) -> Result<Instruction> {
+    let SwapAccounts::AmmV4 {
+        open_orders,
+        target_orders,
+        market: market_id,
+        market_program,
+    } = keys.swap_accounts
+    else {
+        anyhow::bail!("Pool {} is not an AMM v4 pool", keys.pool);
+    };
     let program_id = Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID)
         .context("Failed to parse Raydium AMM v4 program ID")?;
 
-    // Build instruction data: discriminator + amount_in + min_amount_out
-    let mut data = Vec::new();
-    data.extend_from_slice(&discriminators::SWAP);
+    let mut data = Vec::with_capacity(17);
+    data.push(discriminators::SWAP_BASE_IN);
     data.extend_from_slice(&amount_in.to_le_bytes());
     data.extend_from_slice(&min_amount_out.to_le_bytes());
 
-    // Account metas (order matters - verify with IDL)
-    // This is simplified - actual instruction requires many more accounts
     let accounts = vec![
-        AccountMeta::new(*user, true),
-        AccountMeta::new_readonly(*amm, false),
-        AccountMeta::new_readonly(*amm_authority, false),
-        AccountMeta::new(*amm_open_orders, false),
-        AccountMeta::new(*amm_target_orders, false),
-        AccountMeta::new(*pool_coin_token_account, false),
-        AccountMeta::new(*pool_pc_token_account, false),
-        AccountMeta::new(*user_source_token_account, false),
-        AccountMeta::new(*user_dest_token_account, false),
-        AccountMeta::new_readonly(*serum_program_id, false),
-        AccountMeta::new_readonly(*serum_market, false),
-        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(keys.pool, false),
+        AccountMeta::new_readonly(pool::amm_v4_authority(), false),
+        AccountMeta::new(open_orders, false),
+        AccountMeta::new(target_orders, false),
+        AccountMeta::new(keys.base_vault, false),
+        AccountMeta::new(keys.quote_vault, false),
+        AccountMeta::new_readonly(market_program, false),
+        AccountMeta::new(market_id, false),
+        AccountMeta::new(market.bids, false),
+        AccountMeta::new(market.asks, false),
+        AccountMeta::new(market.event_queue, false),
+        AccountMeta::new(market.base_vault, false),
+        AccountMeta::new(market.quote_vault, false),
+        AccountMeta::new_readonly(market.vault_signer, false),
+        AccountMeta::new(*user_source, false),
+        AccountMeta::new(*user_dest, false),
+        AccountMeta::new_readonly(*user_owner, true),
     ];
 
     Ok(Instruction {
@@ -87,36 +95,54 @@ pub fn build_raydium_swap_instruction(
     })
 }
 
-/// Build a Raydium CPMM swap instruction
-/// 
-/// CPMM uses a simpler constant product formula
+/// Build a Raydium CPMM `swap_base_input` instruction selling `input_mint` for the other side
 pub fn build_cpmm_swap_instruction(
-    user: &Pubkey,
-    pool: &Pubkey,
-    user_source_token_account: &Pubkey,
-    user_dest_token_account: &Pubkey,
-    pool_source_token_account: &Pubkey,
-    pool_dest_token_account: &Pubkey,
+    keys: &PoolKeys,
+    payer: &Pubkey,
+    input_mint: &Pubkey,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
     amount_in: u64,
     min_amount_out: u64,
 ) -> Result<Instruction> {
+    let SwapAccounts::Cpmm {
+        amm_config,
+        observation,
+        ..
+    } = keys.swap_accounts
+    else {
+        anyhow::bail!("Pool {} is not a CPMM pool", keys.pool);
+    };
     let program_id = Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_ID)
         .context("Failed to parse Raydium CPMM program ID")?;
 
-    // Build instruction data
-    let mut data = Vec::new();
-    data.extend_from_slice(&discriminators::CPMM_SWAP);
+    let (input_vault, output_vault, output_mint) = if *input_mint == keys.base_mint {
+        (keys.base_vault, keys.quote_vault, keys.quote_mint)
+    } else if *input_mint == keys.quote_mint {
+        (keys.quote_vault, keys.base_vault, keys.base_mint)
+    } else {
+        anyhow::bail!("Mint {} is not in pool {}", input_mint, keys.pool);
+    };
+
+    let mut data = Vec::with_capacity(24);
+    data.extend_from_slice(&discriminators::CPMM_SWAP_BASE_INPUT);
     data.extend_from_slice(&amount_in.to_le_bytes());
     data.extend_from_slice(&min_amount_out.to_le_bytes());
 
     let accounts = vec![
-        AccountMeta::new(*user, true),
-        AccountMeta::new_readonly(*pool, false),
-        AccountMeta::new(*user_source_token_account, false),
-        AccountMeta::new(*user_dest_token_account, false),
-        AccountMeta::new(*pool_source_token_account, false),
-        AccountMeta::new(*pool_dest_token_account, false),
-        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new_readonly(pool::cpmm_authority(), false),
+        AccountMeta::new_readonly(amm_config, false),
+        AccountMeta::new(keys.pool, false),
+        AccountMeta::new(*user_input, false),
+        AccountMeta::new(*user_output, false),
+        AccountMeta::new(input_vault, false),
+        AccountMeta::new(output_vault, false),
+        AccountMeta::new_readonly(keys.token_program(input_mint), false),
+        AccountMeta::new_readonly(keys.token_program(&output_mint), false),
+        AccountMeta::new_readonly(*input_mint, false),
+        AccountMeta::new_readonly(output_mint, false),
+        AccountMeta::new(observation, false),
     ];
 
     Ok(Instruction {
@@ -154,6 +180,21 @@ pub struct PoolCreationData {
     pub creator: Pubkey,
 }
 
+/// Decode an instruction to `program_id` that removes liquidity from a pool
+///
+/// Returns the position of the pool among the instruction's accounts and the
+/// LP tokens burned.
+pub fn liquidity_withdrawal(program_id: &Pubkey, data: &[u8]) -> Option<(usize, u64)> {
+    let program_id = program_id.to_string();
+    if program_id == RAYDIUM_AMM_V4_PROGRAM_ID && data.first() == Some(&discriminators::WITHDRAW) {
+        Some((1, pool::read_u64(data, 1).ok()?))
+    } else if program_id == RAYDIUM_CPMM_PROGRAM_ID && data.starts_with(&discriminators::CPMM_WITHDRAW) {
+        Some((2, pool::read_u64(data, 8).ok()?))
+    } else {
+        None
+    }
+}
+
 /// Check if instruction data matches a pool initialization
 pub fn is_pool_initialization(data: &[u8]) -> bool {
     if data.len() < 8 {
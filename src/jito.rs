@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, system_instruction,
+    transaction::VersionedTransaction,
+};
+use std::str::FromStr;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Default Jito block engine
+pub const DEFAULT_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
+
+/// Jito tip payment accounts (mainnet)
+pub const TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Pick a random tip account
+pub fn random_tip_account() -> Pubkey {
+    let account = TIP_ACCOUNTS
+        .choose(&mut rand::thread_rng())
+        .expect("tip account list is not empty");
+    Pubkey::from_str(account).expect("tip accounts are valid pubkeys")
+}
+
+/// Build a tip transfer to a random tip account
+pub fn tip_instruction(payer: &Pubkey, lamports: u64) -> Instruction {
+    system_instruction::transfer(payer, &random_tip_account(), lamports)
+}
+
+/// Final state of a submitted bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleStatus {
+    Landed { slot: u64 },
+    /// Rejected by the block engine or dropped by the auction
+    Failed(String),
+    /// Still pending when the timeout elapsed
+    TimedOut,
+}
+
+/// JSON-RPC client for the Jito block engine bundle API
+pub struct JitoClient {
+    http: reqwest::Client,
+    bundles_url: String,
+}
+
+impl JitoClient {
+    /// `block_engine_url` is the base URL, e.g. `https://mainnet.block-engine.jito.wtf`
+    /// or a local stub such as `http://127.0.0.1:8080`
+    pub fn new(block_engine_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            bundles_url: format!("{}/api/v1/bundles", block_engine_url.trim_end_matches('/')),
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .http
+            .post(&self.bundles_url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to call block engine {}", method))?
+            .json()
+            .await
+            .with_context(|| format!("Invalid block engine response to {}", method))?;
+
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("Block engine rejected {}: {}", method, error);
+        }
+
+        response
+            .get("result")
+            .cloned()
+            .with_context(|| format!("Block engine response to {} has no result", method))
+    }
+
+    /// Submit a bundle and return its bundle ID
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        let encoded = transactions
+            .iter()
+            .map(|tx| {
+                let bytes = bincode::serialize(tx)?;
+                Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = self
+            .call("sendBundle", json!([encoded, { "encoding": "base64" }]))
+            .await?;
+
+        result
+            .as_str()
+            .map(str::to_string)
+            .context("sendBundle result is not a bundle ID")
+    }
+
+    /// Poll in-flight status until the bundle lands, fails, or `timeout` elapses
+    pub async fn wait_for_bundle(&self, bundle_id: &str, timeout: Duration) -> Result<BundleStatus> {
+        let deadline = Instant::now() + timeout;
+
+        while Instant::now() < deadline {
+            let result = self
+                .call("getInflightBundleStatuses", json!([[bundle_id]]))
+                .await?;
+            let status = &result["value"][0];
+
+            match status["status"].as_str() {
+                Some("Landed") => {
+                    return Ok(BundleStatus::Landed {
+                        slot: status["landed_slot"].as_u64().unwrap_or(0),
+                    })
+                }
+                Some(state @ ("Failed" | "Invalid")) => {
+                    return Ok(BundleStatus::Failed(state.to_string()))
+                }
+                _ => {}
+            }
+
+            sleep(STATUS_POLL_INTERVAL).await;
+        }
+
+        Ok(BundleStatus::TimedOut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::Transaction;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    type Respond = Arc<dyn Fn(&str, &Value) -> Value + Send + Sync>;
+
+    /// Serve JSON-RPC over plain HTTP on a local port; `respond` maps (method, params)
+    /// to the whole response body. Returns the base URL.
+    async fn stub(respond: impl Fn(&str, &Value) -> Value + Send + Sync + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let respond: Respond = Arc::new(respond);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(serve(socket, respond.clone()));
+            }
+        });
+        url
+    }
+
+    async fn serve(socket: TcpStream, respond: Respond) {
+        let mut reader = BufReader::new(socket);
+        let mut line = String::new();
+        // Requests on a kept-alive connection arrive one after another
+        while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+            let mut content_length = 0;
+            loop {
+                line.clear();
+                reader.read_line(&mut line).await.unwrap();
+                let header = line.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await.unwrap();
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let response = respond(request["method"].as_str().unwrap(), &request["params"]).to_string();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            );
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            line.clear();
+        }
+    }
+
+    fn result(value: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 1, "result": value })
+    }
+
+    fn status(state: &str) -> Value {
+        result(json!({
+            "context": { "slot": 100 },
+            "value": [{ "bundle_id": "bundle-1", "status": state, "landed_slot": 99 }],
+        }))
+    }
+
+    fn transaction() -> VersionedTransaction {
+        VersionedTransaction::from(Transaction::default())
+    }
+
+    #[tokio::test]
+    async fn sends_bundle_and_waits_until_landed() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let seen = polls.clone();
+        let url = stub(move |method, params| match method {
+            "sendBundle" => {
+                assert_eq!(params[0].as_array().unwrap().len(), 1);
+                assert_eq!(params[1]["encoding"], "base64");
+                result(json!("bundle-1"))
+            }
+            "getInflightBundleStatuses" => {
+                assert_eq!(params[0][0], "bundle-1");
+                if seen.fetch_add(1, Ordering::SeqCst) == 0 {
+                    status("Pending")
+                } else {
+                    status("Landed")
+                }
+            }
+            other => panic!("unexpected method {}", other),
+        })
+        .await;
+
+        let client = JitoClient::new(&url);
+        let bundle_id = client.send_bundle(&[transaction()]).await.unwrap();
+        assert_eq!(bundle_id, "bundle-1");
+
+        let status = client
+            .wait_for_bundle(&bundle_id, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(status, BundleStatus::Landed { slot: 99 });
+        assert_eq!(polls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_bundle_is_reported() {
+        let url = stub(|_, _| status("Failed")).await;
+        let status = JitoClient::new(&url)
+            .wait_for_bundle("bundle-1", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(status, BundleStatus::Failed("Failed".to_string()));
+    }
+
+    #[tokio::test]
+    async fn pending_bundle_times_out() {
+        let url = stub(|_, _| status("Pending")).await;
+        let status = JitoClient::new(&url)
+            .wait_for_bundle("bundle-1", Duration::from_millis(600))
+            .await
+            .unwrap();
+        assert_eq!(status, BundleStatus::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn rejected_bundle_is_an_error() {
+        let url = stub(|_, _| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32602, "message": "bundle contains an expired blockhash" },
+            })
+        })
+        .await;
+
+        let error = JitoClient::new(&url)
+            .send_bundle(&[transaction()])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("rejected sendBundle"));
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    commitment_config::CommitmentConfig,
+    compute_budget,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program, sysvar,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::config::{RAYDIUM_AMM_V4_PROGRAM_ID, RAYDIUM_CPMM_PROGRAM_ID};
+use crate::jito;
+use crate::pool::{self, WSOL_MINT};
+use crate::wallet::Wallet;
+
+/// OpenBook (Serum v3) program used by AMM v4 markets
+const OPENBOOK_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+
+/// Addresses extended per transaction (keeps the extend instruction well under the size limit)
+const EXTEND_CHUNK: usize = 20;
+
+/// Accounts shared by every buy, which the bot's lookup table should hold
+pub fn static_accounts() -> Vec<Pubkey> {
+    let mut accounts = vec![
+        system_program::id(),
+        compute_budget::id(),
+        sysvar::rent::id(),
+        spl_token::id(),
+        spl_associated_token_account::id(),
+        WSOL_MINT,
+        Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID).expect("valid program ID"),
+        Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_ID).expect("valid program ID"),
+        Pubkey::from_str(OPENBOOK_PROGRAM_ID).expect("valid program ID"),
+        pool::amm_v4_authority(),
+        pool::cpmm_authority(),
+    ];
+    accounts.extend(
+        jito::TIP_ACCOUNTS
+            .iter()
+            .map(|a| Pubkey::from_str(a).expect("tip accounts are valid pubkeys")),
+    );
+    accounts
+}
+
+/// Fetch a lookup table and its addresses
+pub async fn fetch(rpc_client: &RpcClient, address: &Pubkey) -> Result<AddressLookupTableAccount> {
+    let data = rpc_client
+        .get_account_data(address)
+        .await
+        .with_context(|| format!("Failed to fetch lookup table {}", address))?;
+    let table = AddressLookupTable::deserialize(&data)
+        .with_context(|| format!("{} is not an address lookup table", address))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+async fn send(rpc_client: &RpcClient, wallet: &Wallet, instruction: Instruction) -> Result<()> {
+    let blockhash = rpc_client
+        .get_latest_blockhash()
+        .await
+        .context("Failed to get latest blockhash")?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&wallet.pubkey()),
+        &[wallet.keypair()],
+        blockhash,
+    );
+    let signature = rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .await
+        .context("Lookup table transaction failed")?;
+    log::debug!("Lookup table transaction confirmed: {}", signature);
+    Ok(())
+}
+
+/// Create a lookup table owned by the wallet; returns its address
+pub async fn create(rpc_client: &RpcClient, wallet: &Wallet) -> Result<Pubkey> {
+    let recent_slot = rpc_client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await
+        .context("Failed to get recent slot")?;
+    let (instruction, address) = create_lookup_table(wallet.pubkey(), wallet.pubkey(), recent_slot);
+
+    send(rpc_client, wallet, instruction).await?;
+    Ok(address)
+}
+
+/// Add the static accounts and `extra` to the table, skipping ones it already holds
+///
+/// Returns the number of addresses added.
+pub async fn extend(
+    rpc_client: &RpcClient,
+    wallet: &Wallet,
+    address: &Pubkey,
+    extra: &[Pubkey],
+) -> Result<usize> {
+    let existing = fetch(rpc_client, address).await?.addresses;
+
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for account in static_accounts().into_iter().chain(extra.iter().copied()) {
+        if !existing.contains(&account) && !missing.contains(&account) {
+            missing.push(account);
+        }
+    }
+
+    for chunk in missing.chunks(EXTEND_CHUNK) {
+        let instruction =
+            extend_lookup_table(*address, wallet.pubkey(), Some(wallet.pubkey()), chunk.to_vec());
+        send(rpc_client, wallet, instruction).await?;
+    }
+
+    Ok(missing.len())
+}
@@ -1,17 +1,60 @@
+mod blockhash;
+mod broadcast;
+mod candles;
+mod compute;
 mod config;
+mod confirm;
 mod detector;
+mod emergency;
+mod exits;
+mod facts;
+mod fees;
+mod fills;
+mod filter;
+mod filters;
+mod funding;
 mod instructions;
+mod jito;
+mod lookup_table;
+mod metadata;
+mod nonce;
+mod pool;
+mod positions;
+mod quote;
+mod reputation;
+mod rules;
+mod scoring;
 mod sniper;
+mod tips;
+mod token_accounts;
+mod tpu;
+mod tracker;
+mod trades;
 mod utils;
 mod wallet;
 
 use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
 use tokio::signal;
 use tokio_stream::StreamExt;
 
-use config::{CliArgs, Config};
-use detector::PoolDetector;
-use sniper::Sniper;
+use candles::{CandleRecorder, CandleStore, Resolution};
+use compute::ComputeProfile;
+use config::{CliArgs, Command, Config, LookupTableCommand, NonceCommand};
+use detector::{PoolCreationEvent, PoolDetector};
+use emergency::EmergencyWatcher;
+use exits::{ExitEngine, ExitReason};
+use positions::PositionStore;
+use reputation::CreatorStore;
+use rules::{RuleFilter, RuleSet};
+use scoring::BuyPlan;
+use sniper::{BuyReceipt, Sniper};
+use tracker::ReserveTracker;
+use trades::{SellRecord, TradeLog, TradeRecord};
 use utils::init_logging;
 use wallet::Wallet;
 
@@ -33,22 +76,83 @@ async fn main() -> Result<()> {
     let mut config = Config::from_env().context("Failed to load configuration")?;
     config.apply_cli_args(&cli_args);
 
+    let creator_store = Arc::new(
+        CreatorStore::load(&config.creator_db_path).context("Failed to load creator store")?,
+    );
+
+    if let Some(command) = cli_args.command {
+        return run_command(command, &config, &creator_store).await;
+    }
+
     log::info!("Configuration loaded:");
     log::info!("  RPC URL: {}", config.rpc_url);
     if let Some(ref grpc_url) = config.yellowstone_grpc_url {
         log::info!("  Yellowstone gRPC URL: {}", grpc_url);
     }
+    for (name, url) in &config.send_endpoints {
+        log::info!("  Send Endpoint: {} ({})", name, url);
+    }
     log::info!("  Buy Amount: {} SOL", config.buy_amount_sol);
-    log::info!("  Priority Fee: {} micro-lamports", config.priority_fee_micro_lamports);
+    log::info!(
+        "  Priority Fee: p{} of recent fees, {}-{} micro-lamports (fallback {})",
+        config.priority_fee_percentile,
+        config.priority_fee_min_micro_lamports,
+        config.priority_fee_max_micro_lamports,
+        config.priority_fee_micro_lamports
+    );
     log::info!("  Min Liquidity: ${:.2}", config.min_liquidity_usd);
     if let Some(max_liq) = config.max_liquidity_usd {
         log::info!("  Max Liquidity: ${:.2}", max_liq);
     }
     log::info!("  Monitor AMM v4: {}", config.monitor_amm_v4);
     log::info!("  Monitor CPMM: {}", config.monitor_cpmm);
+    log::info!(
+        "  Compute Units: max {}, simulate first: {}, margin {} bps",
+        config.max_compute_units,
+        config.simulate_compute_units,
+        config.compute_unit_margin_bps
+    );
     log::info!("  Dry Run: {}", config.dry_run);
+    log::info!(
+        "  Confirmation: {:?}, rebroadcast every {}ms",
+        config.confirmation_commitment.commitment,
+        config.rebroadcast_interval_ms
+    );
     log::info!("  Jito Enabled: {}", config.jito_enabled);
+    if config.tpu_enabled {
+        log::info!("  TPU Direct Send: {} leaders", config.tpu_leader_fanout);
+    }
+    if config.jito_enabled {
+        log::info!(
+            "  Jito Block Engine: {} (tip p{}, {}-{} lamports)",
+            config
+                .jito_block_engine_url
+                .as_deref()
+                .unwrap_or(jito::DEFAULT_BLOCK_ENGINE_URL),
+            config.jito_tip_percentile,
+            config.jito_tip_min_lamports,
+            config.jito_tip_max_lamports
+        );
+    }
     log::info!("  Blacklisted Creators: {}", config.blacklisted_creators.len());
+    log::info!(
+        "  Metadata Rules: {} name deny, {} name allow, {} symbol deny, {} symbol allow",
+        config.metadata_name_deny.len(),
+        config.metadata_name_allow.len(),
+        config.metadata_symbol_deny.len(),
+        config.metadata_symbol_allow.len()
+    );
+    log::info!("  Reject Mutable Metadata: {}", config.reject_mutable_metadata);
+    log::info!("  Creator Store: {}", config.creator_db_path);
+    log::info!("  Funding Trace Hops: {}", config.funding_trace_hops);
+    if config.scoring_enabled {
+        log::info!(
+            "  Scoring: threshold {:.2}, buy {:.4}-{:.4} SOL",
+            config.score_threshold,
+            config.buy_amount_min_sol.unwrap_or(config.buy_amount_sol),
+            config.buy_amount_max_sol.unwrap_or(config.buy_amount_sol)
+        );
+    }
 
     // Load wallet
     let wallet = Wallet::from_config(&config)
@@ -56,12 +160,94 @@ async fn main() -> Result<()> {
 
     log::info!("Wallet loaded: {}", wallet.pubkey());
 
+    let compute_profile = Arc::new(
+        ComputeProfile::load(
+            &config.compute_profile_path,
+            config.max_compute_units,
+            config.compute_unit_margin_bps,
+        )
+        .context("Failed to load compute profile")?,
+    );
+    tokio::spawn(utils::run_persist(
+        compute_profile.clone(),
+        tokio::time::Duration::from_secs(5),
+    ));
+
     // Check balance
-    let sniper = Sniper::new(
+    let mut sniper = Sniper::new(
         config.rpc_url.clone(),
         wallet.clone(),
         config.clone(),
+        creator_store.clone(),
+        compute_profile.clone(),
+    );
+
+    if config.tpu_enabled {
+        let websocket_url = config
+            .websocket_url
+            .clone()
+            .unwrap_or_else(|| tpu::websocket_url_for(&config.rpc_url));
+        match tpu::TpuSender::new(sniper.rpc_client(), &websocket_url, config.tpu_leader_fanout).await {
+            Ok(sender) => {
+                log::info!("TPU sender connected via {}", websocket_url);
+                sniper.set_tpu_sender(sender);
+            }
+            Err(e) => {
+                log::warn!("Failed to start TPU sender, using RPC only: {}", e);
+            }
+        }
+    }
+
+    if config.use_durable_nonce {
+        let pool = nonce::NoncePool::load(
+            sniper.rpc_client(),
+            wallet.pubkey(),
+            &config.nonce_accounts_path,
+        )
+        .context("Failed to load nonce accounts")?;
+        if pool.account_count() == 0 {
+            log::warn!(
+                "Durable nonces enabled but {} lists no accounts; run `nonce create` first",
+                config.nonce_accounts_path
+            );
+        } else {
+            log::info!("Loaded {} durable nonce accounts", pool.account_count());
+            sniper.set_nonce_pool(Arc::new(pool));
+        }
+    }
+
+    if let Some(ref address) = config.lookup_table_address {
+        let address = address.parse().context("Invalid LOOKUP_TABLE_ADDRESS")?;
+        let table = lookup_table::fetch(&sniper.rpc_client(), &address)
+            .await
+            .context("Failed to load address lookup table")?;
+        log::info!("Loaded lookup table {} ({} addresses)", address, table.addresses.len());
+        sniper.set_lookup_table(table);
+    }
+
+    if let Some(ref rules_path) = config.rules_path {
+        let rules = RuleSet::load(Path::new(rules_path)).context("Failed to load filter rules")?;
+        log::info!("Loaded {} filter rules from {}", rules.rules.len(), rules_path);
+        sniper.add_filter(Box::new(RuleFilter::new(rules, creator_store.clone())));
+    }
+
+    // Positions survive restarts; correct them for anything that changed while we were down
+    let position_store = Arc::new(
+        PositionStore::open(&config.positions_db_path).context("Failed to open position store")?,
     );
+    match position_store
+        .reconcile(&sniper.rpc_client(), &wallet.pubkey(), config.dry_run)
+        .await
+    {
+        Ok(summary) => log::info!(
+            "Positions reconciled: {} unchanged, {} adjusted, {} closed, {} with untracked tokens",
+            summary.unchanged,
+            summary.adjusted,
+            summary.closed,
+            summary.flagged
+        ),
+        Err(e) => log::warn!("Failed to reconcile positions: {}", e),
+    }
 
     let balance = sniper.get_balance().await?;
     log::info!("Wallet balance: {:.4} SOL", balance as f64 / 1_000_000_000.0);
@@ -71,6 +257,47 @@ async fn main() -> Result<()> {
         log::warn!("Low balance detected! Make sure you have enough SOL for buys and fees.");
     }
 
+    // One shared subscription pushes confirmations of every transaction we send
+    tokio::spawn(sniper.signature_watcher().run());
+
+    // Keep a fresh blockhash ready so buys need no RPC round trip
+    tokio::spawn(sniper.blockhash_cache().run_refresh(
+        sniper.rpc_client(),
+        config.yellowstone_grpc_url.clone(),
+        tokio::time::Duration::from_millis(config.blockhash_refresh_ms),
+    ));
+
+    // Keep priority fee estimates warm for the monitored programs
+    let mut fee_programs = Vec::new();
+    if config.monitor_amm_v4 {
+        fee_programs.push(config::RAYDIUM_AMM_V4_PROGRAM_ID.parse()?);
+    }
+    if config.monitor_cpmm {
+        fee_programs.push(config::RAYDIUM_CPMM_PROGRAM_ID.parse()?);
+    }
+    tokio::spawn(sniper.fee_estimator().run_refresh(fee_programs));
+
+    // Track the Jito tip floor
+    if config.jito_enabled {
+        tokio::spawn(
+            sniper
+                .tip_oracle()
+                .run_refresh(tokio::time::Duration::from_secs(10)),
+        );
+    }
+
+    // Resolve launch outcomes for the creator reputation store
+    tokio::spawn(reputation::track_outcomes(
+        creator_store.clone(),
+        config.rpc_url.clone(),
+        config.creator_outcome_window_secs,
+        tokio::time::Duration::from_secs(30),
+    ));
+    tokio::spawn(utils::run_persist(
+        creator_store.clone(),
+        tokio::time::Duration::from_secs(5),
+    ));
+
     // Create pool detector
     let detector = PoolDetector::new(config.clone())
         .context("Failed to create pool detector")?;
@@ -83,9 +310,76 @@ async fn main() -> Result<()> {
         log::info!("Shutdown signal received");
     };
 
+    let sniper = Arc::new(sniper);
+    let trade_log = Arc::new(TradeLog::new(&config.trade_log_path));
+
+    // Stream reserves of held pools so exits are priced without RPC calls
+    let tracker = Arc::new(ReserveTracker::new(sniper.rpc_client(), &config));
+    tokio::spawn(tracker.clone().run());
+
+    // Record price candles of every tracked pool
+    let recorder = CandleRecorder::new(
+        tracker.clone(),
+        CandleStore::new(&config.candles_dir).spawn_writer(),
+    );
+    tokio::spawn(recorder.run());
+
+    // Sell held positions on take-profit and stop-loss
+    let exit_engine = Arc::new(ExitEngine::new(
+        sniper.clone(),
+        position_store.clone(),
+        trade_log.clone(),
+        tracker.clone(),
+        &config,
+    ));
+    if exit_engine.rules().is_empty() {
+        log::info!("No exit rules configured; positions are held until sold manually");
+    } else {
+        log::info!(
+            "Exit rules: {} take-profit tiers, stop-loss {}, trailing stop {} ({} buckets), max hold {} ({} buckets)",
+            exit_engine.rules().take_profits.len(),
+            config
+                .stop_loss_pct
+                .map_or("off".to_string(), |pct| format!("at -{}%", pct)),
+            config
+                .trailing_stop_pct
+                .map_or("off".to_string(), |pct| format!("{}%", pct)),
+            config.trailing_stop_buckets.len(),
+            config
+                .max_hold_secs
+                .map_or("off".to_string(), |secs| format!("{}s", secs)),
+            config.max_hold_buckets.len()
+        );
+        tokio::spawn(
+            exit_engine
+                .clone()
+                .run(tokio::time::Duration::from_millis(config.exit_check_interval_ms)),
+        );
+    }
+
+    // Sell at once if a held pool is being rugged
+    if config.emergency_exit_enabled {
+        log::info!(
+            "Emergency exits on withdrawals, {}% reserve drops and authority changes{}",
+            config.emergency_reserve_drop_pct,
+            if config.emergency_use_jito && config.jito_enabled {
+                " (via Jito)"
+            } else {
+                ""
+            }
+        );
+        let watcher = EmergencyWatcher::new(
+            exit_engine.clone(),
+            position_store.clone(),
+            tracker.clone(),
+            &config,
+        );
+        tokio::spawn(Arc::new(watcher).run());
+    }
+
     // Main detection and snipe loop
     let snipe_handle = tokio::spawn(async move {
-        run_snipe_loop(detector, sniper, config).await;
+        run_snipe_loop(detector, sniper, trade_log, position_store, tracker, config).await;
     });
 
     // Wait for shutdown signal or snipe loop completion
@@ -93,18 +387,316 @@ async fn main() -> Result<()> {
         _ = shutdown_signal => {
             log::info!("Shutting down...");
             snipe_handle.abort();
+            utils::flush(&creator_store).await;
+            utils::flush(&compute_profile).await;
         }
         _ = snipe_handle => {
             log::info!("Snipe loop completed");
+            utils::flush(&creator_store).await;
+            utils::flush(&compute_profile).await;
         }
     }
 
     Ok(())
 }
 
+/// Run a maintenance subcommand and exit
+async fn run_command(command: Command, config: &Config, creator_store: &Arc<CreatorStore>) -> Result<()> {
+    match command {
+        Command::ExportCreators { path } => {
+            creator_store.export(Path::new(&path))?;
+            log::info!("Exported creator store to {}", path);
+        }
+        Command::ImportCreators { path } => {
+            let added = creator_store.import(Path::new(&path))?;
+            log::info!("Imported {} launches from {}", added, path);
+        }
+        Command::Nonce { action } => run_nonce_command(action, config).await?,
+        Command::LookupTable { action } => run_lookup_table_command(action, config).await?,
+        Command::Sell { mint, percent } => {
+            run_sell_command(Some(mint), percent, config, creator_store).await?
+        }
+        Command::SellAll => run_sell_command(None, 100.0, config, creator_store).await?,
+        Command::CloseEmptyAccounts => run_close_accounts_command(config).await?,
+        Command::Candles {
+            mint,
+            resolution,
+            limit,
+        } => run_candles_command(&mint, &resolution, limit, config)?,
+    }
+
+    Ok(())
+}
+
+async fn run_nonce_command(action: NonceCommand, config: &Config) -> Result<()> {
+    let wallet = Wallet::from_config(config).context("Failed to load wallet")?;
+    let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+    let path = Path::new(&config.nonce_accounts_path);
+
+    match action {
+        NonceCommand::Create {
+            count,
+            extra_lamports,
+        } => {
+            let created =
+                nonce::create_accounts(&rpc_client, &wallet, path, count, extra_lamports).await?;
+            log::info!("Created {} nonce accounts in {}", created.len(), path.display());
+        }
+        NonceCommand::List => {
+            for address in nonce::load_accounts(path)? {
+                let balance = rpc_client.get_balance(&address).await.unwrap_or(0);
+                match nonce::fetch_nonce(&rpc_client, &address).await {
+                    Ok(value) => println!("{}  nonce={}  balance={} lamports", address, value, balance),
+                    Err(e) => println!("{}  error: {}", address, e),
+                }
+            }
+        }
+        NonceCommand::Advance { address } => {
+            let accounts = match address {
+                Some(address) => vec![address.parse().context("Invalid nonce account address")?],
+                None => nonce::load_accounts(path)?,
+            };
+            for address in accounts {
+                let signature = nonce::advance(&rpc_client, &wallet, &address).await?;
+                log::info!("Advanced nonce account {} ({})", address, signature);
+            }
+        }
+        NonceCommand::Fund { address, lamports } => {
+            let address = address.parse().context("Invalid nonce account address")?;
+            let signature = nonce::fund(&rpc_client, &wallet, &address, lamports).await?;
+            log::info!("Funded nonce account {} with {} lamports ({})", address, lamports, signature);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_lookup_table_command(action: LookupTableCommand, config: &Config) -> Result<()> {
+    let wallet = Wallet::from_config(config).context("Failed to load wallet")?;
+    let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+    let configured = || -> Result<Pubkey> {
+        config
+            .lookup_table_address
+            .as_deref()
+            .context("LOOKUP_TABLE_ADDRESS is not set")?
+            .parse()
+            .context("Invalid LOOKUP_TABLE_ADDRESS")
+    };
+
+    match action {
+        LookupTableCommand::Create => {
+            let address = lookup_table::create(&rpc_client, &wallet).await?;
+            let added = lookup_table::extend(&rpc_client, &wallet, &address, &[]).await?;
+            log::info!("Created lookup table {} with {} addresses", address, added);
+            println!("LOOKUP_TABLE_ADDRESS={}", address);
+        }
+        LookupTableCommand::Extend { addresses } => {
+            let extra = addresses
+                .iter()
+                .map(|a| a.parse().with_context(|| format!("Invalid address {}", a)))
+                .collect::<Result<Vec<_>>>()?;
+            let address = configured()?;
+            let added = lookup_table::extend(&rpc_client, &wallet, &address, &extra).await?;
+            log::info!("Added {} addresses to lookup table {}", added, address);
+        }
+        LookupTableCommand::Show => {
+            let table = lookup_table::fetch(&rpc_client, &configured()?).await?;
+            for address in &table.addresses {
+                println!("{}", address);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sell one tracked position (or all of them) and print what each sold for
+async fn run_sell_command(
+    mint: Option<String>,
+    percent: f64,
+    config: &Config,
+    creator_store: &Arc<CreatorStore>,
+) -> Result<()> {
+    if !(percent > 0.0 && percent <= 100.0) {
+        anyhow::bail!("--percent must be greater than 0 and at most 100");
+    }
+
+    let wallet = Wallet::from_config(config).context("Failed to load wallet")?;
+    let compute_profile = Arc::new(
+        ComputeProfile::load(
+            &config.compute_profile_path,
+            config.max_compute_units,
+            config.compute_unit_margin_bps,
+        )
+        .context("Failed to load compute profile")?,
+    );
+    let sniper = Arc::new(Sniper::new(
+        config.rpc_url.clone(),
+        wallet.clone(),
+        config.clone(),
+        creator_store.clone(),
+        compute_profile.clone(),
+    ));
+
+    tokio::spawn(sniper.signature_watcher().run());
+
+    // Correct positions for what the wallet actually holds before sizing the sells;
+    // tokens beyond the recorded buys stay untracked and are not sold
+    let position_store = Arc::new(
+        PositionStore::open(&config.positions_db_path).context("Failed to open position store")?,
+    );
+    position_store
+        .reconcile(&sniper.rpc_client(), &wallet.pubkey(), config.dry_run)
+        .await
+        .context("Failed to reconcile positions")?;
+
+    let positions = match mint {
+        Some(mint) => vec![position_store
+            .get(&mint)?
+            .with_context(|| format!("No tracked position in {}", mint))?],
+        None => position_store.all()?,
+    };
+    if positions.is_empty() {
+        println!("No open positions");
+        return Ok(());
+    }
+    if config.dry_run {
+        println!("Dry run: quoting only, nothing will be sent");
+    }
+
+    let trade_log = Arc::new(TradeLog::new(&config.trade_log_path));
+    let tracker = Arc::new(ReserveTracker::new(sniper.rpc_client(), config));
+    let exits = ExitEngine::new(sniper, position_store, trade_log, tracker, config);
+
+    let mut received_lamports = 0u64;
+    for position in positions {
+        let amount = (position.token_amount as f64 * percent / 100.0) as u64;
+        match exits
+            .exit(&position.mint, Some(amount), ExitReason::Manual { percent })
+            .await
+        {
+            Ok(Some(record)) => {
+                received_lamports += print_sell(&record, position.decimals);
+            }
+            Ok(None) => println!("{}  nothing to sell", position.mint),
+            Err(e) => println!("{}  sell failed: {}", position.mint, e),
+        }
+        if position.untracked_tokens > 0 {
+            println!(
+                "{}  {:.4} untracked tokens left in the wallet",
+                position.mint,
+                position.untracked_tokens as f64 / 10f64.powi(position.decimals as i32)
+            );
+        }
+    }
+
+    println!("Total received: {:.6} SOL", received_lamports as f64 / 1_000_000_000.0);
+    utils::flush(&compute_profile).await;
+    Ok(())
+}
+
+/// Print one sell and return the lamports it received (or was quoted, without a fill)
+fn print_sell(record: &SellRecord, decimals: u8) -> u64 {
+    let (lamports, source) = match record.fill {
+        Some(ref fill) => ((-fill.sol_spent_lamports).max(0) as u64, "filled"),
+        None => (record.quoted_lamports, "quoted"),
+    };
+    let tokens = record.token_amount as f64 / 10f64.powi(decimals as i32);
+    let price = if tokens > 0.0 {
+        (lamports as f64 / 1_000_000_000.0) / tokens
+    } else {
+        0.0
+    };
+
+    println!(
+        "{}  sold {:.4} tokens for {:.6} SOL at {:.10} SOL/token ({})  {}",
+        record.mint,
+        tokens,
+        lamports as f64 / 1_000_000_000.0,
+        price,
+        source,
+        record.signature
+    );
+    lamports
+}
+
+/// Close zero-balance token accounts and WSOL accounts, reclaiming their lamports
+async fn run_close_accounts_command(config: &Config) -> Result<()> {
+    let wallet = Wallet::from_config(config).context("Failed to load wallet")?;
+    let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+
+    let accounts = token_accounts::closable_accounts(&rpc_client, &wallet.pubkey()).await?;
+    if accounts.is_empty() {
+        println!("No empty token accounts");
+        return Ok(());
+    }
+
+    let total: u64 = accounts.iter().map(|account| account.lamports).sum();
+    for account in &accounts {
+        println!(
+            "{}  mint={}  amount={}  {:.6} SOL{}",
+            account.address,
+            account.mint,
+            account.amount,
+            account.lamports as f64 / 1_000_000_000.0,
+            if account.program_id == spl_token::id() { "" } else { "  (Token-2022)" }
+        );
+    }
+
+    if config.dry_run {
+        println!(
+            "Dry run: would close {} accounts and reclaim {:.6} SOL",
+            accounts.len(),
+            total as f64 / 1_000_000_000.0
+        );
+        return Ok(());
+    }
+
+    let signatures = token_accounts::close_accounts(&rpc_client, &wallet, &accounts).await?;
+    println!(
+        "Closed {} accounts in {} transactions, reclaimed {:.6} SOL",
+        accounts.len(),
+        signatures.len(),
+        total as f64 / 1_000_000_000.0
+    );
+    Ok(())
+}
+
+/// Print a token's recorded candles as CSV
+fn run_candles_command(mint: &str, resolution: &str, limit: usize, config: &Config) -> Result<()> {
+    let mint = Pubkey::from_str(mint).context("Invalid mint address")?;
+    let resolution = Resolution::from_str(resolution)?;
+    let candles = CandleStore::new(&config.candles_dir).load(&mint, resolution, limit)?;
+
+    if candles.is_empty() {
+        println!("No {} candles recorded for {}", resolution.label(), mint);
+        return Ok(());
+    }
+
+    println!("start,open,high,low,close,net_flow_sol,net_flow_tokens,inflow_slots,outflow_slots");
+    for candle in candles {
+        println!(
+            "{},{},{},{},{},{:.9},{},{},{}",
+            candle.start,
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.net_flow_lamports as f64 / 1_000_000_000.0,
+            candle.net_flow_tokens,
+            candle.inflow_slots,
+            candle.outflow_slots
+        );
+    }
+    Ok(())
+}
+
 async fn run_snipe_loop(
     detector: PoolDetector,
-    sniper: Sniper,
+    sniper: Arc<Sniper>,
+    trade_log: Arc<TradeLog>,
+    position_store: Arc<PositionStore>,
+    tracker: Arc<ReserveTracker>,
     config: Config,
 ) {
     log::info!("Starting pool detection and sniping loop");
@@ -132,24 +724,40 @@ async fn run_snipe_loop(
 
         // Evaluate pool against filters
         match sniper.evaluate_pool(&event).await {
-            Ok(should_snipe) => {
-                if should_snipe {
-                    log::info!("Pool passed filters. Executing buy...");
-                    
-                    match sniper.execute_buy(&event).await {
-                        Ok(tx_sig) => {
-                            log::info!(
-                                "Successfully sniped pool {}: transaction {}",
-                                event.pool,
-                                tx_sig
-                            );
-                        }
-                        Err(e) => {
-                            log::error!("Failed to execute buy for {}: {}", event.pool, e);
-                        }
+            Ok(report) => {
+                if let Some(plan) = sniper.plan_buy(&report) {
+                    log::info!("Pool passed filters (score {}). Executing buy...", plan.score);
+
+                    // Pools that open later get a pre-signed buy sent at open time
+                    if let Some(open_time) = sniper.scheduled_open_time(&event).await {
+                        let sniper = sniper.clone();
+                        let trade_log = trade_log.clone();
+                        let position_store = position_store.clone();
+                        let tracker = tracker.clone();
+                        let dry_run = config.dry_run;
+                        tokio::spawn(async move {
+                            let result = sniper.execute_scheduled_buy(&event, &plan, open_time).await;
+                            record_buy(result, &event, plan, &trade_log, &position_store, &tracker, dry_run);
+                        });
+                    } else {
+                        let result = sniper.execute_buy(&event, &plan).await;
+                        record_buy(
+                            result,
+                            &event,
+                            plan,
+                            &trade_log,
+                            &position_store,
+                            &tracker,
+                            config.dry_run,
+                        );
                     }
-                } else {
-                    log::debug!("Pool did not pass filters: {}", event.pool);
+                } else if let Some(rejection) = report.rejected_by() {
+                    log::info!(
+                        "Pool {} rejected by {} filter: {}",
+                        event.pool,
+                        rejection.name,
+                        rejection.verdict.reasons.join("; ")
+                    );
                 }
             }
             Err(e) => {
@@ -163,3 +771,60 @@ async fn run_snipe_loop(
 
     log::warn!("Event stream ended unexpectedly");
 }
+
+/// Log the result of a buy, append it to the trade log, open a position from its fill
+/// and start recording the pool's price
+fn record_buy(
+    result: Result<BuyReceipt>,
+    event: &PoolCreationEvent,
+    plan: BuyPlan,
+    trade_log: &TradeLog,
+    position_store: &PositionStore,
+    tracker: &Arc<ReserveTracker>,
+    dry_run: bool,
+) {
+    match result {
+        Ok(receipt) => {
+            log::info!(
+                "Successfully sniped pool {}: transaction {}",
+                event.pool,
+                receipt.signature
+            );
+
+            let mut held = false;
+            if let Some(ref fill) = receipt.fill {
+                match position_store.open_from_fill(event, fill, plan.score.total) {
+                    Ok(position) => {
+                        log::info!("Position: {}", position);
+                        held = true;
+                    }
+                    Err(e) => log::error!("Failed to record position in {}: {}", fill.mint, e),
+                }
+            } else if !dry_run {
+                log::error!(
+                    "No fill parsed for {}; its tokens are not tracked as a position",
+                    receipt.signature
+                );
+            }
+
+            // A held pool stays tracked until its position closes; otherwise only for the history window
+            let tracker = tracker.clone();
+            let (pool, pool_type) = (event.pool, event.pool_type);
+            tokio::spawn(async move {
+                match tracker.track(&pool, pool_type).await {
+                    Ok(_) if !held => tracker.release(&pool),
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Failed to track reserves of {}: {}", pool, e),
+                }
+            });
+
+            let record = TradeRecord::new(event, &receipt, plan.amount_sol, dry_run, plan.score);
+            if let Err(e) = trade_log.append(&record) {
+                log::warn!("Failed to record trade: {}", e);
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to execute buy for {}: {}", event.pool, e);
+        }
+    }
+}
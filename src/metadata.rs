@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::config::Config;
+
+/// Metaplex Token Metadata Program ID
+pub const TOKEN_METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// A creator entry from the metadata account
+#[derive(Debug, Clone)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+/// Decoded Metaplex token metadata
+#[derive(Debug, Clone)]
+pub struct TokenMetadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<Creator>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+}
+
+/// Derive the metadata PDA for a mint
+pub fn find_metadata_address(mint: &Pubkey) -> Result<Pubkey> {
+    let program_id = Pubkey::from_str(TOKEN_METADATA_PROGRAM_ID)
+        .context("Failed to parse token metadata program ID")?;
+
+    let (address, _) = Pubkey::find_program_address(
+        &[b"metadata", program_id.as_ref(), mint.as_ref()],
+        &program_id,
+    );
+
+    Ok(address)
+}
+
+impl TokenMetadata {
+    /// Fetch and decode the metadata account for a mint
+    pub async fn fetch(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Self> {
+        let address = find_metadata_address(mint)?;
+        let data = rpc_client
+            .get_account_data(&address)
+            .await
+            .with_context(|| format!("Failed to fetch metadata account for mint {}", mint))?;
+
+        Self::decode(&data)
+    }
+
+    /// Fetch and decode the metadata account for a mint, or `None` if it doesn't exist
+    pub async fn fetch_optional(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Option<Self>> {
+        let address = find_metadata_address(mint)?;
+        let account = rpc_client
+            .get_account_with_commitment(&address, rpc_client.commitment())
+            .await
+            .with_context(|| format!("Failed to fetch metadata account for mint {}", mint))?
+            .value;
+
+        account.map(|account| Self::decode(&account.data)).transpose()
+    }
+
+    /// Decode a metadata account (Borsh layout, trailing fields ignored)
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader { data, offset: 0 };
+
+        // Account key discriminator (4 = MetadataV1)
+        let key = reader.u8()?;
+        if key != 4 {
+            anyhow::bail!("Unexpected metadata account key: {}", key);
+        }
+
+        let update_authority = reader.pubkey()?;
+        let mint = reader.pubkey()?;
+        let name = reader.string()?;
+        let symbol = reader.string()?;
+        let uri = reader.string()?;
+        let seller_fee_basis_points = reader.u16()?;
+
+        let mut creators = Vec::new();
+        if reader.u8()? == 1 {
+            let count = reader.u32()?;
+            for _ in 0..count {
+                creators.push(Creator {
+                    address: reader.pubkey()?,
+                    verified: reader.u8()? != 0,
+                    share: reader.u8()?,
+                });
+            }
+        }
+
+        let primary_sale_happened = reader.u8()? != 0;
+        let is_mutable = reader.u8()? != 0;
+
+        Ok(Self {
+            update_authority,
+            mint,
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points,
+            creators,
+            primary_sale_happened,
+            is_mutable,
+        })
+    }
+}
+
+/// Minimal Borsh reader for the metadata layout
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .context("Metadata account data truncated")?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::new_from_array(self.take(32)?.try_into()?))
+    }
+
+    /// Metaplex pads strings with NULs to a fixed length; strip them
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        Ok(String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string())
+    }
+}
+
+/// Name/symbol and authority rules applied to token metadata
+#[derive(Debug, Clone, Default)]
+pub struct MetadataRules {
+    pub name_deny: Vec<Regex>,
+    pub name_allow: Vec<Regex>,
+    pub symbol_deny: Vec<Regex>,
+    pub symbol_allow: Vec<Regex>,
+    pub reject_mutable: bool,
+    pub reject_creator_update_authority: bool,
+}
+
+impl MetadataRules {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            name_deny: config.metadata_name_deny.clone(),
+            name_allow: config.metadata_name_allow.clone(),
+            symbol_deny: config.metadata_symbol_deny.clone(),
+            symbol_allow: config.metadata_symbol_allow.clone(),
+            reject_mutable: config.reject_mutable_metadata,
+            reject_creator_update_authority: config.reject_creator_update_authority,
+        }
+    }
+
+    /// Whether no rule is configured, so metadata never needs fetching
+    pub fn is_empty(&self) -> bool {
+        self.name_deny.is_empty()
+            && self.name_allow.is_empty()
+            && self.symbol_deny.is_empty()
+            && self.symbol_allow.is_empty()
+            && !self.reject_mutable
+            && !self.reject_creator_update_authority
+    }
+
+    /// Check metadata against the rules
+    ///
+    /// Returns `None` if the metadata passes, or the reason it was rejected
+    pub fn check(&self, metadata: &TokenMetadata, creator: &Pubkey) -> Option<String> {
+        if let Some(reason) = check_patterns("name", &metadata.name, &self.name_deny, &self.name_allow) {
+            return Some(reason);
+        }
+
+        if let Some(reason) =
+            check_patterns("symbol", &metadata.symbol, &self.symbol_deny, &self.symbol_allow)
+        {
+            return Some(reason);
+        }
+
+        if self.reject_mutable && metadata.is_mutable {
+            return Some("metadata is mutable".to_string());
+        }
+
+        if self.reject_creator_update_authority && metadata.update_authority == *creator {
+            return Some(format!(
+                "update authority is still the creator ({})",
+                metadata.update_authority
+            ));
+        }
+
+        None
+    }
+}
+
+/// Apply deny patterns first, then require at least one allow pattern if any are set
+fn check_patterns(field: &str, value: &str, deny: &[Regex], allow: &[Regex]) -> Option<String> {
+    if let Some(pattern) = deny.iter().find(|re| re.is_match(value)) {
+        return Some(format!("{} '{}' matches deny pattern '{}'", field, value, pattern));
+    }
+
+    if !allow.is_empty() && !allow.iter().any(|re| re.is_match(value)) {
+        return Some(format!("{} '{}' matches no allow pattern", field, value));
+    }
+
+    None
+}
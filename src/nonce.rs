@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use solana_client::{nonblocking::rpc_client::RpcClient, nonce_utils::nonblocking as nonce_utils};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    nonce::State as NonceState,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+
+use crate::wallet::Wallet;
+
+/// First wait before retrying a nonce advance that failed, doubled per attempt
+const INVALIDATE_RETRY_BASE: Duration = Duration::from_secs(2);
+
+/// Longest wait between retries of a failed nonce advance
+const INVALIDATE_RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// Load nonce account addresses from a JSON list, empty if the file doesn't exist
+pub fn load_accounts(path: &Path) -> Result<Vec<Pubkey>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read nonce accounts {}", path.display()))?;
+    let addresses: Vec<String> = serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse nonce accounts {}", path.display()))?;
+
+    addresses
+        .iter()
+        .map(|a| Pubkey::from_str(a).with_context(|| format!("Invalid nonce account {}", a)))
+        .collect()
+}
+
+fn save_accounts(path: &Path, accounts: &[Pubkey]) -> Result<()> {
+    let addresses: Vec<String> = accounts.iter().map(Pubkey::to_string).collect();
+    std::fs::write(path, serde_json::to_string_pretty(&addresses)?)
+        .with_context(|| format!("Failed to write nonce accounts {}", path.display()))
+}
+
+/// Read the blockhash currently stored in a nonce account
+pub async fn fetch_nonce(rpc_client: &RpcClient, address: &Pubkey) -> Result<Hash> {
+    let account = nonce_utils::get_account_with_commitment(
+        rpc_client,
+        address,
+        CommitmentConfig::processed(),
+    )
+    .await
+    .with_context(|| format!("Failed to fetch nonce account {}", address))?;
+    let data = nonce_utils::data_from_account(&account)
+        .with_context(|| format!("{} is not an initialized nonce account", address))?;
+
+    Ok(data.blockhash())
+}
+
+/// Advance a nonce account, invalidating any transaction signed against its current value
+pub async fn advance(rpc_client: &RpcClient, wallet: &Wallet, address: &Pubkey) -> Result<Signature> {
+    let blockhash = rpc_client
+        .get_latest_blockhash()
+        .await
+        .context("Failed to get latest blockhash")?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::advance_nonce_account(address, &wallet.pubkey())],
+        Some(&wallet.pubkey()),
+        &[wallet.keypair()],
+        blockhash,
+    );
+
+    rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .await
+        .with_context(|| format!("Failed to advance nonce account {}", address))
+}
+
+/// Transfer `lamports` from the wallet to a nonce account, e.g. to top it up above rent
+pub async fn fund(rpc_client: &RpcClient, wallet: &Wallet, address: &Pubkey, lamports: u64) -> Result<Signature> {
+    // Refuse to send SOL anywhere that isn't a nonce account
+    fetch_nonce(rpc_client, address).await?;
+
+    let blockhash = rpc_client
+        .get_latest_blockhash()
+        .await
+        .context("Failed to get latest blockhash")?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&wallet.pubkey(), address, lamports)],
+        Some(&wallet.pubkey()),
+        &[wallet.keypair()],
+        blockhash,
+    );
+
+    rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .await
+        .with_context(|| format!("Failed to fund nonce account {}", address))
+}
+
+/// Create `count` nonce accounts with the wallet as authority and add them to the pool file
+///
+/// Each account is funded with the rent-exempt minimum plus `extra_lamports`.
+pub async fn create_accounts(
+    rpc_client: &RpcClient,
+    wallet: &Wallet,
+    path: &Path,
+    count: u32,
+    extra_lamports: u64,
+) -> Result<Vec<Pubkey>> {
+    let rent = rpc_client
+        .get_minimum_balance_for_rent_exemption(NonceState::size())
+        .await
+        .context("Failed to get nonce account rent")?;
+
+    let mut accounts = load_accounts(path)?;
+    let mut created = Vec::new();
+
+    for _ in 0..count {
+        let nonce_keypair = Keypair::new();
+        let blockhash = rpc_client
+            .get_latest_blockhash()
+            .await
+            .context("Failed to get latest blockhash")?;
+        let transaction = Transaction::new_signed_with_payer(
+            &system_instruction::create_nonce_account(
+                &wallet.pubkey(),
+                &nonce_keypair.pubkey(),
+                &wallet.pubkey(),
+                rent + extra_lamports,
+            ),
+            Some(&wallet.pubkey()),
+            &[wallet.keypair(), &nonce_keypair],
+            blockhash,
+        );
+
+        let signature = rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .await
+            .context("Failed to create nonce account")?;
+        log::info!("Created nonce account {} ({})", nonce_keypair.pubkey(), signature);
+
+        // Save after each account so a later failure doesn't lose funded accounts
+        accounts.push(nonce_keypair.pubkey());
+        save_accounts(path, &accounts)?;
+        created.push(nonce_keypair.pubkey());
+    }
+
+    Ok(created)
+}
+
+/// Durable nonce accounts owned by the wallet, leased out one transaction at a time
+pub struct NoncePool {
+    rpc_client: Arc<RpcClient>,
+    authority: Pubkey,
+    accounts: Vec<Pubkey>,
+    in_use: Mutex<HashSet<Pubkey>>,
+}
+
+impl NoncePool {
+    /// Load the pool from the accounts file
+    pub fn load(rpc_client: Arc<RpcClient>, authority: Pubkey, path: impl Into<PathBuf>) -> Result<Self> {
+        let accounts = load_accounts(&path.into())?;
+
+        Ok(Self {
+            rpc_client,
+            authority,
+            accounts,
+            in_use: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Lease a free nonce account and read its current nonce
+    ///
+    /// A leased account is never handed out again until the lease is dropped,
+    /// so two in-flight transactions can't share a nonce. Drop the lease only
+    /// once the transaction has landed, failed on-chain, or been invalidated
+    /// with [`NoncePool::invalidate`].
+    pub async fn acquire(self: &Arc<Self>) -> Result<NonceLease> {
+        let address = {
+            let mut in_use = self.in_use.lock().unwrap();
+            let address = self
+                .accounts
+                .iter()
+                .find(|a| !in_use.contains(*a))
+                .copied()
+                .context("All nonce accounts are in use")?;
+            in_use.insert(address);
+            address
+        };
+
+        // The lease releases the account if fetching fails
+        let mut lease = NonceLease {
+            pool: self.clone(),
+            address,
+            nonce: Hash::default(),
+        };
+        lease.nonce = fetch_nonce(&self.rpc_client, &address).await?;
+
+        Ok(lease)
+    }
+
+    /// Advance a leased nonce so a transaction signed against it can never land
+    pub async fn invalidate(&self, lease: &NonceLease, wallet: &Wallet) -> Result<()> {
+        let signature = advance(&self.rpc_client, wallet, &lease.address).await?;
+        log::info!("Advanced nonce account {} to invalidate its pre-signed buy ({})", lease.address, signature);
+        Ok(())
+    }
+
+    /// Keep retrying to invalidate a leased nonce in the background
+    ///
+    /// The account stays leased, and so out of use, until the advance succeeds:
+    /// signing against it again before then could let the old buy land too.
+    pub fn invalidate_in_background(self: &Arc<Self>, lease: NonceLease, wallet: Wallet) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            let mut wait = INVALIDATE_RETRY_BASE;
+            loop {
+                sleep(wait).await;
+                match pool.invalidate(&lease, &wallet).await {
+                    Ok(()) => return,
+                    Err(e) => {
+                        wait = (wait * 2).min(INVALIDATE_RETRY_MAX);
+                        log::warn!(
+                            "Nonce account {} still not invalidated, retrying in {}s: {}",
+                            lease.address,
+                            wait.as_secs(),
+                            e
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Exclusive use of one nonce account
+pub struct NonceLease {
+    pool: Arc<NoncePool>,
+    pub address: Pubkey,
+    /// Nonce to sign with in place of a recent blockhash
+    pub nonce: Hash,
+}
+
+impl NonceLease {
+    /// Must be the first instruction of the transaction
+    pub fn advance_instruction(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.address, &self.pool.authority)
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        self.pool.in_use.lock().unwrap().remove(&self.address);
+    }
+}
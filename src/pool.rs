@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::str::FromStr;
+
+use crate::config::{RAYDIUM_AMM_V4_PROGRAM_ID, RAYDIUM_CPMM_PROGRAM_ID};
+use crate::detector::PoolType;
+
+/// Wrapped SOL mint
+pub const WSOL_MINT: Pubkey = spl_token::native_mint::ID;
+
+/// Token-2022 program, which owns the accounts of mints created with extensions
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Program-wide signer of AMM v4 pool vaults
+pub fn amm_v4_authority() -> Pubkey {
+    let program = Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID).expect("valid program ID");
+    Pubkey::find_program_address(&[b"amm authority"], &program).0
+}
+
+/// Program-wide signer of CPMM pool vaults and LP mints
+pub fn cpmm_authority() -> Pubkey {
+    let program = Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_ID).expect("valid program ID");
+    Pubkey::find_program_address(&[b"vault_and_lp_mint_auth_seed"], &program).0
+}
+
+/// Raydium program that owns pools of `pool_type`
+pub fn program_id(pool_type: PoolType) -> Pubkey {
+    let program = match pool_type {
+        PoolType::AMMv4 => RAYDIUM_AMM_V4_PROGRAM_ID,
+        PoolType::CPMM => RAYDIUM_CPMM_PROGRAM_ID,
+    };
+    Pubkey::from_str(program).expect("valid program ID")
+}
+
+/// AMM v4 `AmmInfo` account size
+const AMM_V4_ACCOUNT_LEN: usize = 752;
+
+/// CPMM `PoolState` account size (including the 8-byte Anchor discriminator)
+const CPMM_ACCOUNT_LEN: usize = 637;
+
+/// Token accounts and mints of a Raydium pool, decoded from its state account
+#[derive(Debug, Clone)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub pool_type: PoolType,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    /// Unix timestamp at which swaps are enabled
+    pub open_time: u64,
+    /// Program-specific accounts a swap needs
+    pub swap_accounts: SwapAccounts,
+}
+
+/// Accounts a swap needs beyond the vaults and mints, by pool program
+#[derive(Debug, Clone)]
+pub enum SwapAccounts {
+    AmmV4 {
+        open_orders: Pubkey,
+        target_orders: Pubkey,
+        market: Pubkey,
+        market_program: Pubkey,
+    },
+    Cpmm {
+        amm_config: Pubkey,
+        observation: Pubkey,
+        base_token_program: Pubkey,
+        quote_token_program: Pubkey,
+    },
+}
+
+/// OpenBook market accounts an AMM v4 swap passes through
+#[derive(Debug, Clone)]
+pub struct MarketKeys {
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub vault_signer: Pubkey,
+}
+
+/// OpenBook `MarketState` size, including the 5-byte head and 7-byte tail padding
+const MARKET_ACCOUNT_LEN: usize = 388;
+
+impl MarketKeys {
+    /// Fetch and decode a market account
+    pub async fn fetch(rpc_client: &RpcClient, market: &Pubkey, market_program: &Pubkey) -> Result<Self> {
+        let data = rpc_client
+            .get_account_data(market)
+            .await
+            .with_context(|| format!("Failed to fetch market account {}", market))?;
+
+        Self::decode(market, market_program, &data)
+    }
+
+    /// Decode an OpenBook `MarketState` account
+    pub fn decode(market: &Pubkey, market_program: &Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < MARKET_ACCOUNT_LEN {
+            anyhow::bail!("Market account too short: {} bytes", data.len());
+        }
+
+        let nonce = read_u64(data, 45)?;
+        let vault_signer =
+            Pubkey::create_program_address(&[market.as_ref(), &nonce.to_le_bytes()], market_program)
+                .context("Invalid market vault signer nonce")?;
+
+        Ok(Self {
+            base_vault: read_pubkey(data, 117)?,
+            quote_vault: read_pubkey(data, 165)?,
+            event_queue: read_pubkey(data, 253)?,
+            bids: read_pubkey(data, 285)?,
+            asks: read_pubkey(data, 317)?,
+            vault_signer,
+        })
+    }
+}
+
+impl PoolKeys {
+    /// Fetch and decode a pool state account
+    pub async fn fetch(rpc_client: &RpcClient, pool: &Pubkey, pool_type: PoolType) -> Result<Self> {
+        let data = rpc_client
+            .get_account_data(pool)
+            .await
+            .with_context(|| format!("Failed to fetch pool account {}", pool))?;
+
+        Self::decode(pool, pool_type, &data)
+    }
+
+    /// Decode a pool state account
+    pub fn decode(pool: &Pubkey, pool_type: PoolType, data: &[u8]) -> Result<Self> {
+        match pool_type {
+            PoolType::AMMv4 => Self::decode_amm_v4(pool, data),
+            PoolType::CPMM => Self::decode_cpmm(pool, data),
+        }
+    }
+
+    /// Decode an AMM v4 `AmmInfo` account
+    fn decode_amm_v4(pool: &Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < AMM_V4_ACCOUNT_LEN {
+            anyhow::bail!("AMM v4 account too short: {} bytes", data.len());
+        }
+
+        Ok(Self {
+            pool: *pool,
+            pool_type: PoolType::AMMv4,
+            open_time: read_u64(data, 224)?,
+            base_vault: read_pubkey(data, 336)?,
+            quote_vault: read_pubkey(data, 368)?,
+            base_mint: read_pubkey(data, 400)?,
+            quote_mint: read_pubkey(data, 432)?,
+            lp_mint: read_pubkey(data, 464)?,
+            swap_accounts: SwapAccounts::AmmV4 {
+                open_orders: read_pubkey(data, 496)?,
+                market: read_pubkey(data, 528)?,
+                market_program: read_pubkey(data, 560)?,
+                target_orders: read_pubkey(data, 592)?,
+            },
+        })
+    }
+
+    /// Decode a CPMM `PoolState` account
+    fn decode_cpmm(pool: &Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < CPMM_ACCOUNT_LEN {
+            anyhow::bail!("CPMM account too short: {} bytes", data.len());
+        }
+
+        Ok(Self {
+            pool: *pool,
+            pool_type: PoolType::CPMM,
+            base_vault: read_pubkey(data, 72)?,
+            quote_vault: read_pubkey(data, 104)?,
+            lp_mint: read_pubkey(data, 136)?,
+            base_mint: read_pubkey(data, 168)?,
+            quote_mint: read_pubkey(data, 200)?,
+            open_time: read_u64(data, 373)?,
+            swap_accounts: SwapAccounts::Cpmm {
+                amm_config: read_pubkey(data, 8)?,
+                base_token_program: read_pubkey(data, 232)?,
+                quote_token_program: read_pubkey(data, 264)?,
+                observation: read_pubkey(data, 296)?,
+            },
+        })
+    }
+
+    /// Token program that owns `mint`'s accounts (AMM v4 only supports SPL Token)
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        match self.swap_accounts {
+            SwapAccounts::Cpmm {
+                base_token_program, ..
+            } if *mint == self.base_mint => base_token_program,
+            SwapAccounts::Cpmm {
+                quote_token_program,
+                ..
+            } if *mint == self.quote_mint => quote_token_program,
+            _ => spl_token::id(),
+        }
+    }
+
+    /// The newly launched token, i.e. the side of the pool that is not WSOL
+    pub fn token_mint(&self) -> Pubkey {
+        if self.base_mint == WSOL_MINT {
+            self.quote_mint
+        } else {
+            self.base_mint
+        }
+    }
+}
+
+/// Vault balances owed to the protocol rather than the pool, as (base, quote)
+///
+/// AMM v4 vaults hold PnL not yet taken (`need_take_pnl_*`); CPMM vaults hold
+/// accrued protocol and fund fees. Subtract these from the vault balances to
+/// get the reserves swaps are priced against.
+pub fn vault_deductions(pool_type: PoolType, data: &[u8]) -> Result<(u64, u64)> {
+    match pool_type {
+        PoolType::AMMv4 => Ok((read_u64(data, 192)?, read_u64(data, 200)?)),
+        PoolType::CPMM => Ok((
+            read_u64(data, 341)?.saturating_add(read_u64(data, 357)?),
+            read_u64(data, 349)?.saturating_add(read_u64(data, 365)?),
+        )),
+    }
+}
+
+/// LP tokens in circulation, from the pool state account
+pub fn lp_supply(pool_type: PoolType, data: &[u8]) -> Result<u64> {
+    match pool_type {
+        PoolType::AMMv4 => read_u64(data, 720),
+        PoolType::CPMM => read_u64(data, 333),
+    }
+}
+
+/// Read a little-endian u64 at `offset`
+pub fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes: [u8; 8] = data
+        .get(offset..offset + 8)
+        .context("Account data too short for u64")?
+        .try_into()?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Read a pubkey at `offset`
+pub fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    let bytes: [u8; 32] = data
+        .get(offset..offset + 32)
+        .context("Account data too short for pubkey")?
+        .try_into()?;
+    Ok(Pubkey::new_from_array(bytes))
+}
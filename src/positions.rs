@@ -0,0 +1,431 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountData;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::detector::{PoolCreationEvent, PoolType};
+use crate::fills::Fill;
+
+/// Tokens held from one or more buys of a mint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub mint: String,
+    pub pool: String,
+    pub pool_type: PoolType,
+    /// Raw token amount held
+    pub token_amount: u64,
+    pub decimals: u8,
+    /// Total SOL spent on the tokens held, fees and tips included
+    pub cost_basis_lamports: u64,
+    pub entry_slot: u64,
+    pub entry_signature: String,
+    pub opened_at: i64,
+    /// Raw token amount before any exits (0 in records written before exits were tracked)
+    #[serde(default)]
+    pub initial_token_amount: u64,
+    /// Number of take-profit tiers already sold
+    #[serde(default)]
+    pub take_profits_hit: usize,
+    /// Weighted score of the pool at entry, which selects the exit bucket
+    #[serde(default)]
+    pub entry_score: Option<f64>,
+    /// Highest value the tokens held have been seen at, in lamports (trailing stop high-water mark);
+    /// 0 until the position is first valued
+    #[serde(default)]
+    pub peak_value_lamports: u64,
+    /// Tokens found on-chain beyond what recorded buys account for; left out of
+    /// the position so they don't dilute its cost basis
+    #[serde(default)]
+    pub untracked_tokens: u64,
+}
+
+impl Position {
+    /// Average SOL paid per whole token
+    pub fn entry_price(&self) -> f64 {
+        let whole_tokens = self.token_amount as f64 / 10f64.powi(self.decimals as i32);
+        if whole_tokens == 0.0 {
+            return 0.0;
+        }
+        (self.cost_basis_lamports as f64 / 1_000_000_000.0) / whole_tokens
+    }
+
+    /// Token amount the position was opened with, which take-profit fractions apply to
+    pub fn initial_amount(&self) -> u64 {
+        if self.initial_token_amount == 0 {
+            self.token_amount
+        } else {
+            self.initial_token_amount
+        }
+    }
+
+    /// Seconds since the position was opened
+    pub fn held_secs(&self) -> u64 {
+        (chrono::Utc::now().timestamp() - self.opened_at).max(0) as u64
+    }
+
+    /// Remove sold tokens, scaling the cost basis and peak value to the tokens still held
+    pub fn reduce(&mut self, tokens_sold: u64) {
+        self.rescale(self.token_amount.saturating_sub(tokens_sold));
+    }
+
+    /// Set the token amount, scaling the cost basis and peak value in proportion
+    pub fn rescale(&mut self, token_amount: u64) {
+        let scale = |lamports: u64| {
+            (lamports as u128 * token_amount as u128 / self.token_amount.max(1) as u128) as u64
+        };
+        self.cost_basis_lamports = scale(self.cost_basis_lamports);
+        self.peak_value_lamports = scale(self.peak_value_lamports);
+        self.token_amount = token_amount;
+    }
+
+    /// Correct the position for an on-chain balance of `balance` tokens
+    fn reconcile(&mut self, balance: u64) -> BalanceCheck {
+        if balance >= self.token_amount {
+            self.untracked_tokens = balance - self.token_amount;
+            return if self.untracked_tokens == 0 {
+                BalanceCheck::Matches
+            } else {
+                BalanceCheck::Surplus {
+                    untracked: self.untracked_tokens,
+                }
+            };
+        }
+
+        let stored = self.token_amount;
+        self.untracked_tokens = 0;
+        self.rescale(balance);
+        BalanceCheck::Reduced { stored }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} tokens, cost {:.4} SOL, entry {:.10} SOL (pool {}, slot {})",
+            self.mint,
+            self.token_amount,
+            self.cost_basis_lamports as f64 / 1_000_000_000.0,
+            self.entry_price(),
+            self.pool,
+            self.entry_slot
+        )?;
+        if self.untracked_tokens > 0 {
+            write!(f, ", {} untracked tokens on-chain", self.untracked_tokens)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of reconciling stored positions against on-chain balances
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    pub unchanged: usize,
+    pub adjusted: usize,
+    pub closed: usize,
+    /// Positions holding more on-chain than recorded, kept at their cost basis
+    pub flagged: usize,
+}
+
+/// Open positions keyed by mint, persisted in an embedded sled database
+pub struct PositionStore {
+    tree: sled::Tree,
+}
+
+impl PositionStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let db = sled::open(path)
+            .with_context(|| format!("Failed to open position store {}", path.display()))?;
+        let tree = db.open_tree("positions").context("Failed to open positions tree")?;
+        Ok(Self { tree })
+    }
+
+    pub fn get(&self, mint: &str) -> Result<Option<Position>> {
+        self.tree
+            .get(mint)?
+            .map(|bytes| serde_json::from_slice(&bytes).context("Corrupt position record"))
+            .transpose()
+    }
+
+    pub fn all(&self) -> Result<Vec<Position>> {
+        self.tree
+            .iter()
+            .values()
+            .map(|bytes| -> Result<Position> {
+                serde_json::from_slice(&bytes?).context("Corrupt position record")
+            })
+            .collect()
+    }
+
+    /// Atomically read-modify-write the position in `mint`
+    ///
+    /// `f` gets the stored position (if any) and returns the one to store, or
+    /// `None` to remove it. The write is a compare-and-swap against what `f`
+    /// saw, so `f` is re-run on the fresh record if another writer got there first.
+    pub fn update(
+        &self,
+        mint: &str,
+        mut f: impl FnMut(Option<Position>) -> Option<Position>,
+    ) -> Result<Option<Position>> {
+        loop {
+            let current = self.tree.get(mint)?;
+            let position = current
+                .as_ref()
+                .map(|bytes| serde_json::from_slice(bytes).context("Corrupt position record"))
+                .transpose()?;
+            let next = f(position);
+            let encoded = next.as_ref().map(serde_json::to_vec).transpose()?;
+
+            if self
+                .tree
+                .compare_and_swap(mint, current, encoded)
+                .context("Failed to write position")?
+                .is_ok()
+            {
+                self.tree.flush().context("Failed to flush position store")?;
+                return Ok(next);
+            }
+        }
+    }
+
+    pub fn remove(&self, mint: &str) -> Result<()> {
+        self.tree.remove(mint).context("Failed to remove position")?;
+        self.tree.flush().context("Failed to flush position store")?;
+        Ok(())
+    }
+
+    /// Open a position from a buy fill, adding to any existing position in the mint
+    pub fn open_from_fill(
+        &self,
+        event: &PoolCreationEvent,
+        fill: &Fill,
+        score: f64,
+    ) -> Result<Position> {
+        let cost = fill.sol_spent_lamports.max(0) as u64;
+
+        let position = self.update(&fill.mint, |current| {
+            Some(match current {
+                Some(mut existing) => {
+                    existing.initial_token_amount =
+                        existing.initial_amount() + fill.tokens_received();
+                    existing.token_amount += fill.tokens_received();
+                    existing.cost_basis_lamports += cost;
+                    existing
+                }
+                None => Position {
+                    mint: fill.mint.clone(),
+                    pool: event.pool.to_string(),
+                    pool_type: event.pool_type,
+                    token_amount: fill.tokens_received(),
+                    decimals: fill.decimals,
+                    cost_basis_lamports: cost,
+                    entry_slot: fill.slot,
+                    entry_signature: fill.signature.clone(),
+                    opened_at: chrono::Utc::now().timestamp(),
+                    initial_token_amount: fill.tokens_received(),
+                    take_profits_hit: 0,
+                    entry_score: Some(score),
+                    // Set from the first value the exit engine observes
+                    peak_value_lamports: 0,
+                    untracked_tokens: 0,
+                },
+            })
+        })?;
+
+        position.context("Position vanished while opening it")
+    }
+
+    /// Bring stored positions in line with the wallet's on-chain token balances
+    ///
+    /// Positions whose tokens are gone are removed, and smaller balances scale
+    /// the cost basis down to the tokens actually held. A larger balance keeps
+    /// the recorded amount and cost basis; the surplus is flagged as untracked.
+    /// With `dry_run` the corrections are only counted and logged, never written.
+    pub async fn reconcile(
+        &self,
+        rpc_client: &RpcClient,
+        wallet: &Pubkey,
+        dry_run: bool,
+    ) -> Result<Reconciliation> {
+        let mut summary = Reconciliation::default();
+
+        for position in self.all()? {
+            let mint = Pubkey::from_str(&position.mint).context("Invalid position mint")?;
+            let balance = match wallet_token_balance(rpc_client, wallet, &mint).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    log::warn!("Could not reconcile position in {}: {}", position.mint, e);
+                    continue;
+                }
+            };
+
+            if balance == 0 {
+                log::warn!("Position in {} no longer held on-chain, closing it", position.mint);
+                if !dry_run {
+                    self.remove(&position.mint)?;
+                }
+                summary.closed += 1;
+                continue;
+            }
+
+            let outcome = if dry_run {
+                Some(position.clone().reconcile(balance))
+            } else {
+                let mut outcome = None;
+                self.update(&position.mint, |current| {
+                    let mut current = current?;
+                    outcome = Some(current.reconcile(balance));
+                    Some(current)
+                })?;
+                outcome
+            };
+
+            match outcome {
+                Some(BalanceCheck::Matches) => summary.unchanged += 1,
+                Some(BalanceCheck::Reduced { stored }) => {
+                    log::warn!(
+                        "Position in {} holds {} tokens on-chain, {} stored; adjusting",
+                        position.mint,
+                        balance,
+                        stored
+                    );
+                    summary.adjusted += 1;
+                }
+                Some(BalanceCheck::Surplus { untracked }) => {
+                    log::warn!(
+                        "Position in {} holds {} more tokens on-chain than recorded; keeping the cost basis and flagging them as untracked",
+                        position.mint,
+                        untracked
+                    );
+                    summary.flagged += 1;
+                }
+                None => {}
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+/// How a stored position compared to the wallet's balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BalanceCheck {
+    Matches,
+    /// Fewer tokens on-chain; the position was scaled down from `stored`
+    Reduced { stored: u64 },
+    /// More tokens on-chain than recorded
+    Surplus { untracked: u64 },
+}
+
+/// Raw balance of `mint` across all of the wallet's token accounts
+pub async fn wallet_token_balance(rpc_client: &RpcClient, wallet: &Pubkey, mint: &Pubkey) -> Result<u64> {
+    let accounts = rpc_client
+        .get_token_accounts_by_owner(wallet, TokenAccountsFilter::Mint(*mint))
+        .await
+        .context("Failed to fetch token accounts")?;
+
+    let mut total = 0u64;
+    for keyed in accounts {
+        let UiAccountData::Json(parsed) = keyed.account.data else {
+            anyhow::bail!("Token account {} was not returned parsed", keyed.pubkey);
+        };
+        let amount = parsed.parsed["info"]["tokenAmount"]["amount"]
+            .as_str()
+            .and_then(|a| a.parse::<u64>().ok())
+            .with_context(|| format!("Token account {} has no amount", keyed.pubkey))?;
+        total += amount;
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn position(token_amount: u64, cost: u64) -> Position {
+        Position {
+            mint: "mint".to_string(),
+            pool: "pool".to_string(),
+            pool_type: PoolType::AMMv4,
+            token_amount,
+            decimals: 6,
+            cost_basis_lamports: cost,
+            entry_slot: 1,
+            entry_signature: "sig".to_string(),
+            opened_at: 0,
+            initial_token_amount: token_amount,
+            take_profits_hit: 0,
+            entry_score: None,
+            peak_value_lamports: cost,
+            untracked_tokens: 0,
+        }
+    }
+
+    fn store() -> PositionStore {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        PositionStore {
+            tree: db.open_tree("positions").unwrap(),
+        }
+    }
+
+    #[test]
+    fn larger_balance_keeps_the_cost_basis() {
+        let mut p = position(1_000, 5_000);
+        assert_eq!(p.reconcile(1_500), BalanceCheck::Surplus { untracked: 500 });
+        assert_eq!(p.token_amount, 1_000);
+        assert_eq!(p.cost_basis_lamports, 5_000);
+        assert_eq!(p.untracked_tokens, 500);
+    }
+
+    #[test]
+    fn smaller_balance_scales_the_cost_basis() {
+        let mut p = position(1_000, 5_000);
+        assert_eq!(p.reconcile(400), BalanceCheck::Reduced { stored: 1_000 });
+        assert_eq!(p.token_amount, 400);
+        assert_eq!(p.cost_basis_lamports, 2_000);
+        assert_eq!(p.reconcile(400), BalanceCheck::Matches);
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let store = Arc::new(store());
+        store.update("mint", |_| Some(position(0, 0))).unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        store
+                            .update("mint", |current| {
+                                let mut p = current?;
+                                p.token_amount += 1;
+                                Some(p)
+                            })
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(store.get("mint").unwrap().unwrap().token_amount, 400);
+    }
+
+    #[test]
+    fn update_returning_none_removes_the_position() {
+        let store = store();
+        store.update("mint", |_| Some(position(10, 10))).unwrap();
+        assert!(store.update("mint", |_| None).unwrap().is_none());
+        assert!(store.get("mint").unwrap().is_none());
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::program_pack::Pack;
+use spl_token::state::Account as TokenAccount;
+
+use crate::pool::{PoolKeys, WSOL_MINT};
+
+/// Raydium trade fee taken from the input amount, in basis points
+pub const TRADE_FEE_BPS: u64 = 25;
+
+/// Constant-product output for `amount_in`, after the trade fee
+pub fn amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+    let in_after_fee = amount_in as u128 * (10_000 - TRADE_FEE_BPS) as u128 / 10_000;
+    let denominator = reserve_in as u128 + in_after_fee;
+    if denominator == 0 {
+        return 0;
+    }
+    (reserve_out as u128 * in_after_fee / denominator) as u64
+}
+
+/// SOL and token reserves of a pool, in raw units
+///
+/// Read from the vault balances. For AMM v4 this ignores funds parked in
+/// OpenBook orders, which is negligible for freshly launched pools.
+#[derive(Debug, Clone, Copy)]
+pub struct Reserves {
+    pub sol: u64,
+    pub token: u64,
+}
+
+impl Reserves {
+    pub async fn fetch(rpc_client: &RpcClient, keys: &PoolKeys) -> Result<Self> {
+        let accounts = rpc_client
+            .get_multiple_accounts(&[keys.base_vault, keys.quote_vault])
+            .await
+            .context("Failed to fetch pool vaults")?;
+
+        let balance = |i: usize| -> Result<u64> {
+            let account = accounts[i].as_ref().context("Pool vault not found")?;
+            Ok(TokenAccount::unpack_from_slice(&account.data)
+                .context("Invalid pool vault account")?
+                .amount)
+        };
+        Ok(Self::from_sides(keys, balance(0)?, balance(1)?))
+    }
+
+    /// Reserves from raw base/quote amounts, oriented by which side is WSOL
+    pub fn from_sides(keys: &PoolKeys, base: u64, quote: u64) -> Self {
+        if keys.base_mint == WSOL_MINT {
+            Self { sol: base, token: quote }
+        } else {
+            Self { sol: quote, token: base }
+        }
+    }
+
+    /// Spot price in SOL per whole token, before fees
+    pub fn price(&self, token_decimals: u8) -> f64 {
+        let whole_tokens = self.token as f64 / 10f64.powi(token_decimals as i32);
+        if whole_tokens == 0.0 {
+            return 0.0;
+        }
+        (self.sol as f64 / 1_000_000_000.0) / whole_tokens
+    }
+
+    /// Tokens received for `lamports_in`
+    pub fn buy_out(&self, lamports_in: u64) -> u64 {
+        amount_out(lamports_in, self.sol, self.token)
+    }
+
+    /// Lamports received for `tokens_in`
+    pub fn sell_out(&self, tokens_in: u64) -> u64 {
+        amount_out(tokens_in, self.token, self.sol)
+    }
+}
@@ -0,0 +1,456 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use tokio::time::{sleep, Duration};
+
+use crate::detector::{PoolCreationEvent, PoolType};
+use crate::pool::{self, PoolKeys, WSOL_MINT};
+use crate::utils::{self, Persisted};
+
+/// Fraction of initial liquidity (or LP supply) that must be removed for a launch to count as a rug
+const RUG_LIQUIDITY_DROP: f64 = 0.9;
+
+/// Most pending launches checked per round; the least recently checked go first
+const MAX_CHECKS_PER_ROUND: usize = 100;
+
+/// Pending launches checked at once
+const CHECK_CONCURRENCY: usize = 8;
+
+/// What happened to a pool after launch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaunchOutcome {
+    /// Still inside the observation window
+    Pending,
+    /// Liquidity stayed in the pool for the whole window
+    Survived,
+    /// Liquidity was pulled within the window
+    Rugged,
+}
+
+/// A single pool launched by a creator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub pool: String,
+    pub pool_type: PoolType,
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: i64,
+    /// WSOL-side vault balance when the launch was detected
+    pub initial_liquidity_lamports: Option<u64>,
+    /// WSOL-side vault balance at the last check
+    pub last_liquidity_lamports: Option<u64>,
+    /// LP supply recorded by the pool when the launch was detected
+    ///
+    /// Read from the pool state rather than the LP mint: only withdrawals reduce
+    /// it, while burning LP tokens (which locks liquidity in) does not.
+    pub initial_lp_supply: Option<u64>,
+    /// LP supply recorded by the pool at the last check
+    pub last_lp_supply: Option<u64>,
+    /// Unix timestamp of the last outcome check (0 if never checked)
+    #[serde(default)]
+    pub last_checked: i64,
+    pub outcome: LaunchOutcome,
+}
+
+impl LaunchRecord {
+    /// Record a liquidity check, resolving the outcome if it is now known
+    ///
+    /// A launch is rugged once either its WSOL-side liquidity or its LP supply
+    /// has fallen by `RUG_LIQUIDITY_DROP`; the LP supply catches withdrawals that
+    /// leave the SOL side padded, the liquidity catches the creator dumping into the pool.
+    /// Without a baseline from detection time the launch can't be judged and stays pending.
+    fn observe(&mut self, liquidity: u64, lp_supply: u64, now: i64, expired: bool) {
+        self.last_liquidity_lamports = Some(liquidity);
+        self.last_lp_supply = Some(lp_supply);
+        self.last_checked = now;
+        let (Some(initial_liquidity), Some(initial_lp_supply)) =
+            (self.initial_liquidity_lamports, self.initial_lp_supply)
+        else {
+            return;
+        };
+
+        let drained = |initial: u64, current: u64| {
+            initial > 0 && current as f64 <= initial as f64 * (1.0 - RUG_LIQUIDITY_DROP)
+        };
+        if drained(initial_liquidity, liquidity) || drained(initial_lp_supply, lp_supply) {
+            self.outcome = LaunchOutcome::Rugged;
+        } else if expired {
+            self.outcome = LaunchOutcome::Survived;
+        }
+    }
+}
+
+/// Every launch observed from one creator
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreatorHistory {
+    pub launches: Vec<LaunchRecord>,
+}
+
+impl CreatorHistory {
+    pub fn rug_count(&self) -> usize {
+        self.launches
+            .iter()
+            .filter(|l| l.outcome == LaunchOutcome::Rugged)
+            .count()
+    }
+
+    pub fn resolved_count(&self) -> usize {
+        self.launches
+            .iter()
+            .filter(|l| l.outcome != LaunchOutcome::Pending)
+            .count()
+    }
+
+    /// Reputation score in [0, 1]; creators with no resolved launches score 0.5
+    pub fn score(&self) -> f64 {
+        let resolved = self.resolved_count();
+        if resolved == 0 {
+            return 0.5;
+        }
+
+        // Laplace-smoothed survival rate so one launch doesn't swing the score to 0 or 1
+        let survived = resolved - self.rug_count();
+        (survived as f64 + 1.0) / (resolved as f64 + 2.0)
+    }
+}
+
+/// Persistent store of creator launch history, keyed by creator address
+///
+/// Changes are kept in memory and written by `utils::run_persist`, so recording
+/// a launch never touches the disk on the snipe path.
+pub struct CreatorStore {
+    path: PathBuf,
+    creators: RwLock<HashMap<String, CreatorHistory>>,
+    /// Changed since the last write
+    dirty: AtomicBool,
+}
+
+impl CreatorStore {
+    /// Load the store from disk, starting empty if the file doesn't exist
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let creators = if path.exists() {
+            let data = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read creator store {}", path.display()))?;
+            serde_json::from_str(&data)
+                .with_context(|| format!("Failed to parse creator store {}", path.display()))?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            path,
+            creators: RwLock::new(creators),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// Export the store as JSON
+    pub fn export(&self, path: &Path) -> Result<()> {
+        let data = {
+            let creators = self.creators.read().unwrap();
+            serde_json::to_string_pretty(&*creators)?
+        };
+        utils::write_atomic(path, data.as_bytes())
+    }
+
+    /// Merge creator history from a JSON export, skipping launches already recorded
+    pub fn import(&self, path: &Path) -> Result<usize> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let imported: HashMap<String, CreatorHistory> = serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        let mut added = 0;
+        {
+            let mut creators = self.creators.write().unwrap();
+            for (creator, history) in imported {
+                let entry = creators.entry(creator).or_default();
+                for launch in history.launches {
+                    if !entry.launches.iter().any(|l| l.pool == launch.pool) {
+                        entry.launches.push(launch);
+                        added += 1;
+                    }
+                }
+            }
+        }
+
+        self.save()?;
+        Ok(added)
+    }
+
+    /// Record a newly detected launch and snapshot its liquidity in the background
+    ///
+    /// The snapshot is the baseline later checks compare against, so it is taken
+    /// now rather than at the first check, which may come after the creator has
+    /// already pulled liquidity.
+    pub fn record_launch(self: &Arc<Self>, rpc_client: Arc<RpcClient>, event: &PoolCreationEvent) {
+        let creator = event.creator.to_string();
+        let pool = event.pool.to_string();
+        {
+            let mut creators = self.creators.write().unwrap();
+            let history = creators.entry(creator.clone()).or_default();
+            if history.launches.iter().any(|l| l.pool == pool) {
+                return;
+            }
+
+            history.launches.push(LaunchRecord {
+                pool: pool.clone(),
+                pool_type: event.pool_type,
+                signature: event.signature.clone(),
+                slot: event.slot,
+                timestamp: event.timestamp,
+                initial_liquidity_lamports: None,
+                last_liquidity_lamports: None,
+                initial_lp_supply: None,
+                last_lp_supply: None,
+                last_checked: 0,
+                outcome: LaunchOutcome::Pending,
+            });
+            self.mark_dirty();
+        }
+
+        let store = self.clone();
+        let pool_type = event.pool_type;
+        tokio::spawn(async move {
+            match snapshot_liquidity(&rpc_client, &pool, pool_type).await {
+                Ok((liquidity, lp_supply)) => store.update_launch(&creator, &pool, |launch| {
+                    launch.initial_liquidity_lamports = Some(liquidity);
+                    launch.initial_lp_supply = Some(lp_supply);
+                }),
+                Err(e) => log::debug!("Failed to snapshot launch liquidity for {}: {}", pool, e),
+            }
+        });
+    }
+
+    /// Look up a creator's history
+    pub fn history(&self, creator: &Pubkey) -> Option<CreatorHistory> {
+        self.creators.read().unwrap().get(&creator.to_string()).cloned()
+    }
+
+    /// Check whether a creator should be rejected based on past launches
+    ///
+    /// Returns the reason if so
+    pub fn check_creator(&self, creator: &Pubkey, max_rugs: usize, min_score: f64) -> Option<String> {
+        let history = self.history(creator)?;
+        let rugs = history.rug_count();
+        let score = history.score();
+
+        if rugs >= max_rugs {
+            return Some(format!(
+                "creator has rugged {} of {} launches",
+                rugs,
+                history.launches.len()
+            ));
+        }
+
+        if history.resolved_count() > 0 && score < min_score {
+            return Some(format!(
+                "creator reputation {:.2} below minimum {:.2}",
+                score, min_score
+            ));
+        }
+
+        None
+    }
+
+    /// Up to `limit` pending launches with a baseline as (creator, pool, pool_type, timestamp),
+    /// least recently checked first
+    fn pending(&self, limit: usize) -> Vec<(String, String, PoolType, i64)> {
+        let creators = self.creators.read().unwrap();
+        let mut pending: Vec<_> = creators
+            .iter()
+            .flat_map(|(creator, history)| {
+                history
+                    .launches
+                    .iter()
+                    .filter(|l| {
+                        l.outcome == LaunchOutcome::Pending && l.initial_liquidity_lamports.is_some()
+                    })
+                    .map(move |l| (creator, l))
+            })
+            .collect();
+        pending.sort_by_key(|(_, l)| (l.last_checked, l.timestamp));
+        pending
+            .into_iter()
+            .take(limit)
+            .map(|(creator, l)| (creator.clone(), l.pool.clone(), l.pool_type, l.timestamp))
+            .collect()
+    }
+
+    fn update_launch(&self, creator: &str, pool: &str, update: impl FnOnce(&mut LaunchRecord)) {
+        let mut creators = self.creators.write().unwrap();
+        if let Some(launch) = creators
+            .get_mut(creator)
+            .and_then(|h| h.launches.iter_mut().find(|l| l.pool == pool))
+        {
+            update(launch);
+            self.mark_dirty();
+        }
+    }
+}
+
+impl Persisted for CreatorStore {
+    const NAME: &'static str = "creator store";
+
+    fn dirty(&self) -> &AtomicBool {
+        &self.dirty
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = {
+            let creators = self.creators.read().unwrap();
+            serde_json::to_vec(&*creators)?
+        };
+        utils::write_atomic(&self.path, &data)
+    }
+}
+
+/// Periodically check pending launches and resolve their outcome
+///
+/// A launch is marked rugged if its WSOL-side liquidity or LP supply drops by
+/// 90% or more within `window_secs`, and survived once the window passes without
+/// that happening. Each round checks at most `MAX_CHECKS_PER_ROUND` launches, so
+/// a burst of launches spreads its RPC load over several rounds.
+pub async fn track_outcomes(
+    store: Arc<CreatorStore>,
+    rpc_url: String,
+    window_secs: u64,
+    interval: Duration,
+) {
+    let rpc_client = RpcClient::new(rpc_url);
+
+    loop {
+        let pending = store.pending(MAX_CHECKS_PER_ROUND);
+        let mut checks = stream::iter(pending)
+            .map(|(creator, pool, pool_type, timestamp)| {
+                let rpc_client = &rpc_client;
+                async move {
+                    let snapshot = snapshot_liquidity(rpc_client, &pool, pool_type).await;
+                    (creator, pool, timestamp, snapshot)
+                }
+            })
+            .buffer_unordered(CHECK_CONCURRENCY);
+
+        while let Some((creator, pool, timestamp, snapshot)) = checks.next().await {
+            let now = chrono::Utc::now().timestamp();
+            let (liquidity, lp_supply) = match snapshot {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::debug!("Failed to snapshot liquidity for {}: {}", pool, e);
+                    // Still counts as checked, so a dead pool doesn't hold up the others
+                    store.update_launch(&creator, &pool, |launch| launch.last_checked = now);
+                    continue;
+                }
+            };
+
+            let expired = now - timestamp >= window_secs as i64;
+            store.update_launch(&creator, &pool, |launch| {
+                launch.observe(liquidity, lp_supply, now, expired);
+                if launch.outcome == LaunchOutcome::Rugged {
+                    log::info!("Creator {} pulled liquidity from pool {}", creator, pool);
+                }
+            });
+        }
+
+        sleep(interval).await;
+    }
+}
+
+/// Fetch (WSOL-side vault balance, LP supply recorded by the pool) for a pool
+async fn snapshot_liquidity(
+    rpc_client: &RpcClient,
+    pool: &str,
+    pool_type: PoolType,
+) -> Result<(u64, u64)> {
+    let pool: Pubkey = pool.parse().context("Invalid pool address")?;
+    let data = rpc_client
+        .get_account_data(&pool)
+        .await
+        .with_context(|| format!("Failed to fetch pool account {}", pool))?;
+    let keys = PoolKeys::decode(&pool, pool_type, &data)?;
+    let lp_supply = pool::lp_supply(pool_type, &data)?;
+    let vault = if keys.base_mint == WSOL_MINT {
+        keys.base_vault
+    } else {
+        keys.quote_vault
+    };
+
+    let liquidity = rpc_client
+        .get_token_account_balance(&vault)
+        .await
+        .context("Failed to fetch vault balance")?
+        .amount
+        .parse::<u64>()?;
+
+    Ok((liquidity, lp_supply))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch() -> LaunchRecord {
+        LaunchRecord {
+            pool: "pool".to_string(),
+            pool_type: PoolType::CPMM,
+            signature: "sig".to_string(),
+            slot: 1,
+            timestamp: 0,
+            initial_liquidity_lamports: Some(1_000),
+            last_liquidity_lamports: None,
+            initial_lp_supply: Some(500),
+            last_lp_supply: None,
+            last_checked: 0,
+            outcome: LaunchOutcome::Pending,
+        }
+    }
+
+    #[test]
+    fn checks_keep_the_baseline() {
+        let mut launch = launch();
+        launch.observe(900, 450, 10, false);
+        assert_eq!(launch.initial_liquidity_lamports, Some(1_000));
+        assert_eq!(launch.initial_lp_supply, Some(500));
+        assert_eq!(launch.last_liquidity_lamports, Some(900));
+        assert_eq!(launch.last_checked, 10);
+        assert_eq!(launch.outcome, LaunchOutcome::Pending);
+    }
+
+    #[test]
+    fn drained_before_the_first_check_is_a_rug() {
+        let mut launch = launch();
+        launch.observe(100, 500, 10, false);
+        assert_eq!(launch.outcome, LaunchOutcome::Rugged);
+    }
+
+    #[test]
+    fn no_baseline_stays_pending() {
+        let mut launch = launch();
+        launch.initial_liquidity_lamports = None;
+        launch.initial_lp_supply = None;
+        launch.observe(0, 0, 10, true);
+        assert_eq!(launch.initial_liquidity_lamports, None);
+        assert_eq!(launch.outcome, LaunchOutcome::Pending);
+    }
+
+    #[test]
+    fn withdrawn_lp_supply_is_a_rug() {
+        let mut launch = launch();
+        launch.observe(900, 40, 20, false);
+        assert_eq!(launch.outcome, LaunchOutcome::Rugged);
+    }
+
+    #[test]
+    fn survives_once_the_window_passes() {
+        let mut launch = launch();
+        launch.observe(600, 400, 20, true);
+        assert_eq!(launch.outcome, LaunchOutcome::Survived);
+    }
+}
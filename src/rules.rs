@@ -0,0 +1,599 @@
+//! Declarative filter rules
+//!
+//! A rules file holds one rule per line, optionally named:
+//!
+//! ```text
+//! # Comments start with '#'
+//! min-liquidity: liquidity_usd >= 5000
+//! holders: top10_share < 0.3 && !mint_authority
+//! creator_rugs == 0 || creator_score > 0.8
+//! ```
+//!
+//! Expressions support `&&`, `||`, `!`, parentheses, the comparisons
+//! `== != < <= > >=`, numbers, `true`/`false` and the facts listed in
+//! [`PoolFacts::FIELDS`]. A pool passes only if every rule holds.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::facts::{FactType, FactValue, PoolFacts};
+use crate::filter::{CostTier, FilterContext, FilterVerdict, PoolFilter};
+use crate::reputation::CreatorStore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for CmpOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        })
+    }
+}
+
+/// Parsed rule expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Bool(bool),
+    Number(f64),
+    Fact(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Static type of the expression; facts are validated at parse time
+    fn ty(&self) -> FactType {
+        match self {
+            Expr::Number(_) => FactType::Number,
+            Expr::Fact(name) => PoolFacts::type_of(name).unwrap_or(FactType::Number),
+            Expr::Bool(_) | Expr::Not(_) | Expr::And(..) | Expr::Or(..) | Expr::Cmp(..) => {
+                FactType::Bool
+            }
+        }
+    }
+
+    fn eval(&self, facts: &PoolFacts) -> FactValue {
+        match self {
+            Expr::Bool(b) => FactValue::Bool(*b),
+            Expr::Number(n) => FactValue::Number(*n),
+            Expr::Fact(name) => facts.value(name).unwrap_or(FactValue::Number(0.0)),
+            Expr::Not(e) => FactValue::Bool(!e.eval_bool(facts)),
+            Expr::And(a, b) => FactValue::Bool(a.eval_bool(facts) && b.eval_bool(facts)),
+            Expr::Or(a, b) => FactValue::Bool(a.eval_bool(facts) || b.eval_bool(facts)),
+            Expr::Cmp(op, a, b) => FactValue::Bool(match (a.eval(facts), b.eval(facts)) {
+                (FactValue::Number(x), FactValue::Number(y)) => match op {
+                    CmpOp::Eq => x == y,
+                    CmpOp::Ne => x != y,
+                    CmpOp::Lt => x < y,
+                    CmpOp::Le => x <= y,
+                    CmpOp::Gt => x > y,
+                    CmpOp::Ge => x >= y,
+                },
+                (FactValue::Bool(x), FactValue::Bool(y)) => match op {
+                    CmpOp::Ne => x != y,
+                    _ => x == y,
+                },
+                _ => false,
+            }),
+        }
+    }
+
+    pub fn eval_bool(&self, facts: &PoolFacts) -> bool {
+        matches!(self.eval(facts), FactValue::Bool(true))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Cmp(CmpOp),
+    Not,
+    And,
+    Or,
+    Minus,
+    LParen,
+    RParen,
+}
+
+/// Parse error with the 0-based column it refers to
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, SyntaxError> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        let start = i;
+        let two = src.get(i..i + 2).unwrap_or("");
+
+        let token = match c {
+            ' ' | '\t' => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '-' => Token::Minus,
+            _ if two == "&&" => Token::And,
+            _ if two == "||" => Token::Or,
+            _ if two == "==" => Token::Cmp(CmpOp::Eq),
+            _ if two == "!=" => Token::Cmp(CmpOp::Ne),
+            _ if two == "<=" => Token::Cmp(CmpOp::Le),
+            _ if two == ">=" => Token::Cmp(CmpOp::Ge),
+            '<' => Token::Cmp(CmpOp::Lt),
+            '>' => Token::Cmp(CmpOp::Gt),
+            '!' => Token::Not,
+            '0'..='9' | '.' => {
+                while i < bytes.len() && matches!(bytes[i], b'0'..=b'9' | b'.' | b'_') {
+                    i += 1;
+                }
+                let text = src[start..i].replace('_', "");
+                let n = text
+                    .parse::<f64>()
+                    .map_err(|_| SyntaxError::new(start, format!("invalid number '{}'", text)))?;
+                tokens.push((start, Token::Number(n)));
+                continue;
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push((start, Token::Ident(src[start..i].to_string())));
+                continue;
+            }
+            '=' | '&' | '|' => {
+                return Err(SyntaxError::new(
+                    start,
+                    format!("unexpected '{}' (did you mean '{}{}'?)", c, c, c),
+                ))
+            }
+            _ => return Err(SyntaxError::new(start, format!("unexpected character '{}'", c))),
+        };
+
+        i += match token {
+            Token::And | Token::Or => 2,
+            Token::Cmp(op) if op != CmpOp::Lt && op != CmpOp::Gt => 2,
+            _ => 1,
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over a token list
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map(|(c, _)| *c).unwrap_or(self.len)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect_bool(&self, expr: &Expr, column: usize, context: &str) -> Result<(), SyntaxError> {
+        if expr.ty() != FactType::Bool {
+            return Err(SyntaxError::new(
+                column,
+                format!("{} needs a boolean, found a number", context),
+            ));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr, SyntaxError> {
+        let column = self.column();
+        let mut lhs = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.expect_bool(&lhs, column, "'||'")?;
+            self.next();
+            let rhs_column = self.column();
+            let rhs = self.and()?;
+            self.expect_bool(&rhs, rhs_column, "'||'")?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, SyntaxError> {
+        let column = self.column();
+        let mut lhs = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.expect_bool(&lhs, column, "'&&'")?;
+            self.next();
+            let rhs_column = self.column();
+            let rhs = self.unary()?;
+            self.expect_bool(&rhs, rhs_column, "'&&'")?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, SyntaxError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            let column = self.column();
+            let expr = self.unary()?;
+            self.expect_bool(&expr, column, "'!'")?;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, SyntaxError> {
+        let lhs_column = self.column();
+        let lhs = self.primary()?;
+
+        let Some(Token::Cmp(op)) = self.peek().cloned() else {
+            return Ok(lhs);
+        };
+        let op_column = self.column();
+        self.next();
+        let rhs = self.primary()?;
+
+        if lhs.ty() != rhs.ty() {
+            return Err(SyntaxError::new(
+                lhs_column,
+                format!("cannot compare a {:?} with a {:?}", lhs.ty(), rhs.ty()).to_lowercase(),
+            ));
+        }
+        if lhs.ty() == FactType::Bool && !matches!(op, CmpOp::Eq | CmpOp::Ne) {
+            return Err(SyntaxError::new(
+                op_column,
+                format!("'{}' only applies to numbers", op),
+            ));
+        }
+
+        Ok(Expr::Cmp(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, SyntaxError> {
+        let column = self.column();
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Minus) => match self.next() {
+                Some(Token::Number(n)) => Ok(Expr::Number(-n)),
+                _ => Err(SyntaxError::new(column, "expected a number after '-'")),
+            },
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                _ if PoolFacts::type_of(&name).is_some() => Ok(Expr::Fact(name)),
+                _ => Err(SyntaxError::new(column, unknown_fact_message(&name))),
+            },
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(SyntaxError::new(column, "unclosed '('")),
+                }
+            }
+            Some(token) => Err(SyntaxError::new(column, format!("unexpected {:?}", token))),
+            None => Err(SyntaxError::new(column, "unexpected end of rule")),
+        }
+    }
+}
+
+/// Suggest a known fact for typos
+fn unknown_fact_message(name: &str) -> String {
+    let suggestion = PoolFacts::FIELDS
+        .iter()
+        .map(|(field, _)| *field)
+        .min_by_key(|field| edit_distance(name, field))
+        .filter(|field| edit_distance(name, field) <= 3);
+
+    match suggestion {
+        Some(field) => format!("unknown fact '{}' (did you mean '{}'?)", name, field),
+        None => format!("unknown fact '{}'", name),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Parse and type-check a single boolean expression
+pub fn parse_expr(src: &str) -> Result<Expr, SyntaxError> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        len: src.len(),
+    };
+
+    let expr = parser.or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(SyntaxError::new(parser.column(), "unexpected trailing input"));
+    }
+    parser.expect_bool(&expr, 0, "a rule")?;
+
+    Ok(expr)
+}
+
+/// A named rule from a rules file
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub source: String,
+    pub expr: Expr,
+}
+
+/// Rules loaded from a file; a pool passes only if every rule holds
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Load and validate a rules file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read rules file {}: {}", path.display(), e))?;
+        Self::parse(&text, &path.display().to_string())
+    }
+
+    /// Parse rules text; `origin` names the source in error messages
+    pub fn parse(text: &str, origin: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = raw_line.split('#').next().unwrap_or("");
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (name, source, offset) = match line.split_once(':') {
+                Some((name, source)) => {
+                    let name = name.trim();
+                    if name.is_empty() || name.contains(char::is_whitespace) {
+                        anyhow::bail!(
+                            "{}:{}: invalid rule name '{}'\n    {}",
+                            origin,
+                            line_no,
+                            name,
+                            raw_line
+                        );
+                    }
+                    (name.to_string(), source, line.len() - source.len())
+                }
+                None => (format!("line {}", line_no), line, 0),
+            };
+
+            let expr = parse_expr(source).map_err(|e| {
+                anyhow::anyhow!(
+                    "{}:{}: rule '{}': {}\n    {}\n    {}^",
+                    origin,
+                    line_no,
+                    name,
+                    e.message,
+                    raw_line,
+                    " ".repeat(offset + e.column)
+                )
+            })?;
+
+            rules.push(Rule {
+                name,
+                source: source.trim().to_string(),
+                expr,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Rules that do not hold for the given facts
+    pub fn failing(&self, facts: &PoolFacts) -> Vec<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| !rule.expr.eval_bool(facts))
+            .collect()
+    }
+}
+
+/// Filter that gathers [`PoolFacts`] and applies a [`RuleSet`]
+pub struct RuleFilter {
+    rules: RuleSet,
+    creator_store: Arc<CreatorStore>,
+}
+
+impl RuleFilter {
+    pub fn new(rules: RuleSet, creator_store: Arc<CreatorStore>) -> Self {
+        Self {
+            rules,
+            creator_store,
+        }
+    }
+}
+
+#[async_trait]
+impl PoolFilter for RuleFilter {
+    fn name(&self) -> &str {
+        "rules"
+    }
+
+    fn cost(&self) -> CostTier {
+        CostTier::Expensive
+    }
+
+    async fn evaluate(&self, ctx: &FilterContext) -> Result<FilterVerdict> {
+        let facts = PoolFacts::collect(ctx, &self.creator_store).await?;
+        let failing: Vec<String> = self
+            .rules
+            .failing(&facts)
+            .into_iter()
+            .map(|rule| format!("rule '{}' failed: {}", rule.name, rule.source))
+            .collect();
+
+        if failing.is_empty() {
+            return Ok(FilterVerdict::pass_with(
+                1.0,
+                format!("{} rules passed", self.rules.rules.len()),
+            ));
+        }
+
+        let passed = self.rules.rules.len() - failing.len();
+        Ok(FilterVerdict {
+            pass: false,
+            reasons: failing,
+            score: Some(passed as f64 / self.rules.rules.len() as f64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts() -> PoolFacts {
+        PoolFacts {
+            liquidity_usd: 8000.0,
+            top10_share: 0.25,
+            mint_authority: false,
+            freeze_authority: true,
+            creator_rugs: 1.0,
+            open_delay_secs: -5.0,
+            ..Default::default()
+        }
+    }
+
+    fn eval(src: &str) -> bool {
+        parse_expr(src).unwrap().eval_bool(&facts())
+    }
+
+    #[test]
+    fn every_field_has_a_value() {
+        let facts = PoolFacts::default();
+        for (name, ty) in PoolFacts::FIELDS {
+            let value = facts.value(name).unwrap();
+            match ty {
+                FactType::Bool => assert!(matches!(value, FactValue::Bool(_))),
+                FactType::Number => assert!(matches!(value, FactValue::Number(_))),
+            }
+        }
+    }
+
+    #[test]
+    fn evaluates_comparisons_and_logic() {
+        assert!(eval("liquidity_usd >= 5000 && top10_share < 0.3 && !mint_authority"));
+        assert!(!eval("liquidity_usd >= 10_000"));
+        assert!(eval("liquidity_usd > 10000 || creator_rugs == 1"));
+        assert!(eval("!(freeze_authority && creator_rugs > 0) || true"));
+        assert!(eval("freeze_authority == true && mint_authority != true"));
+        assert!(eval("open_delay_secs < -1"));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(eval("true || false && false"));
+        assert!(!eval("(true || false) && false"));
+    }
+
+    #[test]
+    fn rejects_unknown_facts_with_suggestion() {
+        let err = parse_expr("liquidty_usd >= 5000").unwrap_err();
+        assert_eq!(err.column, 0);
+        assert!(err.message.contains("did you mean 'liquidity_usd'"));
+    }
+
+    #[test]
+    fn rejects_type_errors() {
+        assert!(parse_expr("liquidity_usd").is_err());
+        assert!(parse_expr("!liquidity_usd").is_err());
+        assert!(parse_expr("mint_authority > 1").is_err());
+        assert!(parse_expr("mint_authority < true").is_err());
+        assert!(parse_expr("liquidity_usd > 1 && 5").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_syntax() {
+        assert_eq!(parse_expr("liquidity_usd >= 5000 &").unwrap_err().column, 22);
+        assert!(parse_expr("(liquidity_usd > 1").is_err());
+        assert!(parse_expr("liquidity_usd > 1 top10_share").is_err());
+        assert!(parse_expr("").is_err());
+    }
+
+    #[test]
+    fn parses_rules_file() {
+        let text = "\
+# comment
+min-liq: liquidity_usd >= 5000   # trailing comment
+
+top10_share < 0.3
+";
+        let rules = RuleSet::parse(text, "rules.txt").unwrap();
+        assert_eq!(rules.rules.len(), 2);
+        assert_eq!(rules.rules[0].name, "min-liq");
+        assert_eq!(rules.rules[1].name, "line 4");
+        assert!(rules.failing(&facts()).is_empty());
+    }
+
+    #[test]
+    fn reports_offending_rule_and_line() {
+        let text = "ok: true\nbad: top10_share < \n";
+        let err = RuleSet::parse(text, "rules.txt").unwrap_err().to_string();
+        assert!(err.starts_with("rules.txt:2: rule 'bad':"), "{}", err);
+    }
+
+    #[test]
+    fn lists_failing_rules() {
+        let rules = RuleSet::parse("a: creator_rugs == 0\nb: top10_share < 0.5", "t").unwrap();
+        let failing: Vec<_> = rules.failing(&facts()).iter().map(|r| r.name.clone()).collect();
+        assert_eq!(failing, vec!["a"]);
+    }
+}
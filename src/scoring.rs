@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::Config;
+use crate::filter::EvaluationReport;
+
+/// One filter's contribution to a pool's score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreComponent {
+    pub filter: String,
+    pub weight: f64,
+    pub score: f64,
+    /// `weight * score`
+    pub points: f64,
+}
+
+/// Weighted score of a pool across all filters that judged it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScoreCard {
+    /// Weighted average of filter scores in [0, 1]
+    pub total: f64,
+    pub components: Vec<ScoreComponent>,
+}
+
+impl fmt::Display for ScoreCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.3} [", self.total)?;
+        for (i, c) in self.components.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={:.2}x{:.1}", c.filter, c.score, c.weight)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// What to buy once a pool has been evaluated
+#[derive(Debug, Clone)]
+pub struct BuyPlan {
+    pub amount_sol: f64,
+    pub score: ScoreCard,
+}
+
+/// Turns evaluation reports into buy decisions
+///
+/// With scoring disabled every pool without a hard reject is bought at
+/// `buy_amount_sol`. With scoring enabled the pool must also reach
+/// `score_threshold`, and the buy size scales linearly from
+/// `buy_amount_min_sol` at the threshold to `buy_amount_max_sol` at a perfect score.
+#[derive(Debug, Clone)]
+pub struct Scorer {
+    enabled: bool,
+    weights: HashMap<String, f64>,
+    threshold: f64,
+    buy_amount_sol: f64,
+    min_buy_sol: f64,
+    max_buy_sol: f64,
+}
+
+impl Scorer {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            enabled: config.scoring_enabled,
+            weights: config.filter_weights.clone(),
+            threshold: config.score_threshold,
+            buy_amount_sol: config.buy_amount_sol,
+            min_buy_sol: config.buy_amount_min_sol.unwrap_or(config.buy_amount_sol),
+            max_buy_sol: config.buy_amount_max_sol.unwrap_or(config.buy_amount_sol),
+        }
+    }
+
+    /// Weight for a filter (unlisted filters weigh 1.0)
+    fn weight(&self, filter: &str) -> f64 {
+        self.weights.get(filter).copied().unwrap_or(1.0)
+    }
+
+    /// Compute the weighted score for a report
+    ///
+    /// Filters without a score (skipped, or errored with `FILTER_ERRORS_PASS`)
+    /// are left out of both the points and the total weight.
+    pub fn score(&self, report: &EvaluationReport) -> ScoreCard {
+        let components: Vec<ScoreComponent> = report
+            .results
+            .iter()
+            .filter_map(|r| {
+                let score = r.verdict.score?;
+                let weight = self.weight(&r.name);
+                Some(ScoreComponent {
+                    filter: r.name.clone(),
+                    weight,
+                    score,
+                    points: weight * score,
+                })
+            })
+            .collect();
+
+        let total_weight: f64 = components.iter().map(|c| c.weight).sum();
+        let total = if total_weight > 0.0 {
+            components.iter().map(|c| c.points).sum::<f64>() / total_weight
+        } else {
+            0.0
+        };
+
+        ScoreCard { total, components }
+    }
+
+    /// Decide whether and how much to buy
+    pub fn plan(&self, report: &EvaluationReport) -> Option<BuyPlan> {
+        if !report.passed {
+            return None;
+        }
+
+        let score = self.score(report);
+        if !self.enabled {
+            return Some(BuyPlan {
+                amount_sol: self.buy_amount_sol,
+                score,
+            });
+        }
+
+        if score.total < self.threshold {
+            log::info!(
+                "Score {:.3} below threshold {:.3}: {}",
+                score.total,
+                self.threshold,
+                score
+            );
+            return None;
+        }
+
+        let span = (1.0 - self.threshold).max(f64::EPSILON);
+        let t = ((score.total - self.threshold) / span).clamp(0.0, 1.0);
+        let amount_sol = self.min_buy_sol + (self.max_buy_sol - self.min_buy_sol) * t;
+
+        Some(BuyPlan { amount_sol, score })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{FilterResult, FilterVerdict};
+    use std::time::Duration;
+
+    fn report(verdicts: Vec<(&str, FilterVerdict)>) -> EvaluationReport {
+        EvaluationReport {
+            passed: true,
+            results: verdicts
+                .into_iter()
+                .map(|(name, verdict)| FilterResult {
+                    name: name.to_string(),
+                    verdict,
+                    latency: Duration::ZERO,
+                })
+                .collect(),
+            total_latency: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn unscored_filters_are_left_out_of_the_average() {
+        let scorer = Scorer::from_config(&Config::default());
+        let card = scorer.score(&report(vec![
+            ("liquidity", FilterVerdict::pass_with(0.8, "deep")),
+            ("reputation", FilterVerdict::skip("no history for creator")),
+            ("metadata", FilterVerdict::pass_with(0.4, "ok")),
+        ]));
+
+        assert_eq!(card.components.len(), 2);
+        assert!((card.total - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weights_apply_to_scored_filters_only() {
+        let mut config = Config::default();
+        config.filter_weights.insert("funding".to_string(), 5.0);
+        config.filter_weights.insert("liquidity".to_string(), 3.0);
+        let scorer = Scorer::from_config(&config);
+        let card = scorer.score(&report(vec![
+            ("funding", FilterVerdict::skip("funding trace disabled")),
+            ("liquidity", FilterVerdict::pass_with(1.0, "deep")),
+            ("metadata", FilterVerdict::pass_with(0.0, "bare")),
+        ]));
+
+        assert!((card.total - 0.75).abs() < 1e-9);
+    }
+}
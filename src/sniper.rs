@@ -1,45 +1,260 @@
 use anyhow::{Context, Result};
-use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig},
+};
 use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
     pubkey::Pubkey,
-    signature::Signer,
-    system_program,
+    signature::{Signature, Signer},
+    system_instruction, system_program,
     transaction::VersionedTransaction,
 };
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use solana_transaction_status::{TransactionStatus, UiTransactionEncoding};
+use std::collections::HashSet;
 use std::str::FromStr;
-use tokio::time::{sleep, Duration};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration, Instant};
 
+use crate::blockhash::BlockhashCache;
+use crate::broadcast::{BroadcastResult, Broadcaster};
+use crate::compute::ComputeProfile;
 use crate::config::Config;
+use crate::confirm::{ConfirmationSource, SendOutcome, SignatureNotice, SignatureWatcher};
 use crate::detector::{PoolCreationEvent, PoolType};
-use crate::instructions::{build_cpmm_swap_instruction, build_raydium_swap_instruction};
+use crate::exits::ExitReason;
+use crate::fees::PriorityFeeEstimator;
+use crate::fills::{self, Fill};
+use crate::filter::{EvaluationReport, FilterContext, FilterPipeline, PoolFilter};
+use crate::filters::{
+    BlacklistFilter, FundingFilter, LiquidityFilter, MetadataFilter, ReputationFilter, RugFilter,
+};
+use crate::instructions::{build_amm_v4_swap_instruction, build_cpmm_swap_instruction};
+use crate::jito::{self, BundleStatus, JitoClient};
+use crate::nonce::{NonceLease, NoncePool};
+use crate::pool::{self, MarketKeys, PoolKeys, WSOL_MINT};
+use crate::positions::Position;
+use crate::quote::Reserves;
+use crate::reputation::CreatorStore;
+use crate::scoring::{BuyPlan, Scorer};
+use crate::tips::TipOracle;
+use crate::tpu::{self, TpuSender};
 use crate::utils;
 use crate::wallet::Wallet;
 
+/// While the signature stream is live, poll signature status only every this many send intervals
+const POLL_FALLBACK_TICKS: u32 = 5;
+
+/// A buy that was sent and landed (or simulated in dry-run mode)
+#[derive(Debug, Clone)]
+pub struct BuyReceipt {
+    pub signature: String,
+    /// Time from first send to the target commitment, and how it was observed
+    pub confirmation: Option<(Duration, ConfirmationSource)>,
+    /// What the buy actually received and paid, if it could be parsed
+    pub fill: Option<Fill>,
+}
+
+/// A sell that was sent and landed (or simulated in dry-run mode)
+#[derive(Debug, Clone)]
+pub struct SellReceipt {
+    pub signature: String,
+    /// SOL the pre-trade quote expected, in lamports
+    pub quoted_lamports: u64,
+    /// What the sell actually gave up and received, if it could be parsed
+    pub fill: Option<Fill>,
+}
+
+/// Which way a swap between SOL and the pool's token goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SwapSide {
+    /// SOL in, tokens out
+    Buy,
+    /// Tokens in, SOL out
+    Sell,
+}
+
+/// A signed buy and what it was built from
+struct SignedBuy {
+    transaction: VersionedTransaction,
+    /// Jito tip the transaction pays, if any
+    tip: Option<u64>,
+    /// Token being bought
+    mint: Pubkey,
+    /// Tokens the pre-trade quote expected
+    quoted_tokens: u64,
+    /// Priority fee the CU limit saves versus the maximum, counted if the buy lands
+    fee_saved: u64,
+}
+
 /// Sniper that evaluates and executes buys on new pools
 pub struct Sniper {
-    rpc_client: RpcClient,
+    rpc_client: Arc<RpcClient>,
     wallet: Wallet,
     config: Config,
+    pipeline: FilterPipeline,
+    scorer: Scorer,
+    fee_estimator: Arc<PriorityFeeEstimator>,
+    compute_profile: Arc<ComputeProfile>,
+    blockhash_cache: Arc<BlockhashCache>,
+    broadcaster: Broadcaster,
+    signature_watcher: Arc<SignatureWatcher>,
+    tpu_sender: Option<TpuSender>,
+    nonce_pool: Option<Arc<NoncePool>>,
+    lookup_table: Option<AddressLookupTableAccount>,
+    jito_client: Option<JitoClient>,
+    tip_oracle: Arc<TipOracle>,
+    creator_store: Arc<CreatorStore>,
 }
 
 impl Sniper {
-    pub fn new(rpc_url: String, wallet: Wallet, config: Config) -> Self {
-        let rpc_client = RpcClient::new_with_commitment(
+    pub fn new(
+        rpc_url: String,
+        wallet: Wallet,
+        config: Config,
+        creator_store: Arc<CreatorStore>,
+        compute_profile: Arc<ComputeProfile>,
+    ) -> Self {
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(
             rpc_url,
             CommitmentConfig::confirmed(),
-        );
+        ));
+
+        let blacklisted_creators =
+            Arc::new(parse_pubkey_set(&config.blacklisted_creators, "creator"));
+        let blacklisted_funders = Arc::new(parse_pubkey_set(&config.blacklisted_funders, "funder"));
+
+        let pipeline = FilterPipeline::new()
+            .pass_on_error(config.filter_errors_pass)
+            .with(BlacklistFilter::new(blacklisted_creators.clone()))
+            .with(ReputationFilter::new(creator_store.clone(), &config))
+            .with(FundingFilter::new(
+                creator_store.clone(),
+                blacklisted_creators,
+                blacklisted_funders,
+                &config,
+            ))
+            .with(LiquidityFilter::new(&config))
+            .with(RugFilter)
+            .with(MetadataFilter::new(&config));
+
+        let scorer = Scorer::from_config(&config);
+        let fee_estimator = Arc::new(PriorityFeeEstimator::new(rpc_client.clone(), &config));
+        let jito_client = config.jito_enabled.then(|| {
+            JitoClient::new(
+                config
+                    .jito_block_engine_url
+                    .as_deref()
+                    .unwrap_or(jito::DEFAULT_BLOCK_ENGINE_URL),
+            )
+        });
+        let tip_oracle = Arc::new(TipOracle::new(&config));
+        let blockhash_cache = Arc::new(BlockhashCache::new(Duration::from_millis(
+            config.blockhash_stale_ms,
+        )));
+        let broadcaster = Broadcaster::from_config(&config);
+        let signature_watcher = Arc::new(SignatureWatcher::new(
+            config.yellowstone_grpc_url.clone(),
+            config
+                .websocket_url
+                .clone()
+                .unwrap_or_else(|| tpu::websocket_url_for(&config.rpc_url)),
+            config.confirmation_commitment,
+            wallet.pubkey(),
+            rpc_client.clone(),
+        ));
 
         Self {
             rpc_client,
             wallet,
             config,
+            pipeline,
+            scorer,
+            fee_estimator,
+            compute_profile,
+            blockhash_cache,
+            broadcaster,
+            signature_watcher,
+            tpu_sender: None,
+            nonce_pool: None,
+            lookup_table: None,
+            jito_client,
+            tip_oracle,
+            creator_store,
+        }
+    }
+
+    /// Priority fee estimator, for starting its background refresh
+    pub fn fee_estimator(&self) -> Arc<PriorityFeeEstimator> {
+        self.fee_estimator.clone()
+    }
+
+    /// Jito tip oracle, for starting its tip floor refresh
+    pub fn tip_oracle(&self) -> Arc<TipOracle> {
+        self.tip_oracle.clone()
+    }
+
+    /// Shared blockhash cache (run its refresh task to keep it fresh)
+    pub fn blockhash_cache(&self) -> Arc<BlockhashCache> {
+        self.blockhash_cache.clone()
+    }
+
+    /// Shared signature watcher (run it to get pushed confirmations)
+    pub fn signature_watcher(&self) -> Arc<SignatureWatcher> {
+        self.signature_watcher.clone()
+    }
+
+    /// Shared RPC client
+    pub fn rpc_client(&self) -> Arc<RpcClient> {
+        self.rpc_client.clone()
+    }
+
+    /// Send buys directly to leader TPUs, with RPC as fallback
+    pub fn set_tpu_sender(&mut self, sender: TpuSender) {
+        self.tpu_sender = Some(sender);
+    }
+
+    /// Pre-sign buys for pools that open later against durable nonces
+    pub fn set_nonce_pool(&mut self, pool: Arc<NoncePool>) {
+        self.nonce_pool = Some(pool);
+    }
+
+    /// Compress buys with the bot's address lookup table
+    pub fn set_lookup_table(&mut self, table: AddressLookupTableAccount) {
+        self.lookup_table = Some(table);
+    }
+
+    /// Opening time of the pool if it is in the future and buys can be pre-signed for it
+    pub async fn scheduled_open_time(&self, event: &PoolCreationEvent) -> Option<i64> {
+        self.nonce_pool.as_ref()?;
+        match PoolKeys::fetch(&self.rpc_client, &event.pool, event.pool_type).await {
+            Ok(keys) => {
+                let open_time = keys.open_time as i64;
+                (open_time > chrono::Utc::now().timestamp()).then_some(open_time)
+            }
+            Err(e) => {
+                log::debug!("Could not read open time for {}: {}", event.pool, e);
+                None
+            }
         }
     }
 
+    /// Add a custom filter to the evaluation pipeline
+    pub fn add_filter(&mut self, filter: Box<dyn PoolFilter>) {
+        self.pipeline.add(filter);
+    }
+
     /// Evaluate if a pool should be sniped based on filters
-    pub async fn evaluate_pool(&self, event: &PoolCreationEvent) -> Result<bool> {
+    pub async fn evaluate_pool(&self, event: &PoolCreationEvent) -> Result<EvaluationReport> {
         log::info!(
             "Evaluating new pool: pool={}, creator={}, type={:?}",
             event.pool,
@@ -47,273 +262,865 @@ impl Sniper {
             event.pool_type
         );
 
-        // Check blacklist
-        let creator_str = event.creator.to_string();
-        if self.config.blacklisted_creators.contains(&creator_str) {
-            log::info!("Pool creator is blacklisted: {}", creator_str);
-            return Ok(false);
+        let ctx = FilterContext::new(event.clone(), self.rpc_client.clone());
+        let report = self.pipeline.run(&ctx).await;
+
+        // Record every launch in the creator's history, whatever the verdict,
+        // after the filters so they judge the creator on earlier launches only
+        self.creator_store.record_launch(self.rpc_client.clone(), event);
+
+        log::info!("Evaluation report for {}: {}", event.pool, report);
+
+        Ok(report)
+    }
+
+    /// Decide whether to buy an evaluated pool, and how much
+    pub fn plan_buy(&self, report: &EvaluationReport) -> Option<BuyPlan> {
+        self.scorer.plan(report)
+    }
+
+    /// Execute a buy on a pool
+    pub async fn execute_buy(&self, event: &PoolCreationEvent, plan: &BuyPlan) -> Result<BuyReceipt> {
+        if self.config.dry_run {
+            log::info!(
+                "[DRY RUN] Would buy from pool: pool={}, amount={:.4} SOL, score={}",
+                event.pool,
+                plan.amount_sol,
+                plan.score
+            );
+            return Ok(BuyReceipt {
+                signature: "dry_run_simulation".to_string(),
+                confirmation: None,
+                fill: None,
+            });
         }
 
-        // Check liquidity
-        match self.check_liquidity(&event.pool, &event.pool_type).await {
-            Ok(has_sufficient_liquidity) => {
-                if !has_sufficient_liquidity {
-                    log::info!("Pool does not meet liquidity requirements: {}", event.pool);
-                    return Ok(false);
+        log::info!(
+            "Executing buy: pool={}, amount={:.4} SOL, type={:?}, score={}",
+            event.pool,
+            plan.amount_sol,
+            event.pool_type,
+            plan.score
+        );
+
+        // Latest blockhash from the background cache, or RPC if it's empty/stale
+        let (blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+
+        let buy = self.build_signed_buy(event, plan, blockhash, None).await?;
+        let outcome = self
+            .send_swap(buy.transaction.clone(), buy.tip, last_valid_block_height)
+            .await;
+        self.settle_tip(buy.tip, &outcome);
+
+        self.finish_buy(event, &buy, outcome?).await
+    }
+
+    /// Pre-sign a buy against a durable nonce and send it once the pool opens
+    ///
+    /// If the buy doesn't land, its nonce is advanced before the lease is
+    /// released so the pre-signed transaction can never land later.
+    pub async fn execute_scheduled_buy(
+        &self,
+        event: &PoolCreationEvent,
+        plan: &BuyPlan,
+        open_time: i64,
+    ) -> Result<BuyReceipt> {
+        let nonce_pool = match (&self.nonce_pool, self.config.dry_run) {
+            (Some(pool), false) => pool,
+            _ => return self.execute_buy(event, plan).await,
+        };
+
+        let lease = nonce_pool.acquire().await?;
+        let buy = self
+            .build_signed_buy(event, plan, lease.nonce, Some(&lease))
+            .await?;
+
+        let wait = (open_time - chrono::Utc::now().timestamp()).max(0) as u64;
+        log::info!(
+            "Pre-signed buy {} for pool {} with nonce account {}, sending in {}s",
+            buy.transaction.signatures[0],
+            event.pool,
+            lease.address,
+            wait
+        );
+        sleep(Duration::from_secs(wait)).await;
+
+        // A nonce transaction never expires; bound re-broadcasting by a fresh blockhash's lifetime
+        let sent_at = Instant::now();
+        let mut outcome = match self.latest_blockhash().await {
+            Ok((_, deadline)) => self.send_swap(buy.transaction.clone(), buy.tip, deadline).await,
+            Err(e) => Err(e),
+        };
+
+        // Landed or failed on-chain both consume the nonce; otherwise invalidate it
+        if !matches!(outcome, Ok(SendOutcome::Landed { .. } | SendOutcome::Failed { .. })) {
+            match nonce_pool.invalidate(&lease, &self.wallet).await {
+                // The buy can't land any more, but it may have landed before the nonce advanced
+                Ok(()) => {
+                    let signature = buy.transaction.signatures[0];
+                    if let Some(settled) = self.settled_status(signature, sent_at).await {
+                        log::info!("Scheduled buy {} was processed before its nonce advanced", signature);
+                        outcome = Ok(settled);
+                    }
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to invalidate nonce account {}; keeping it leased and retrying in the background: {}",
+                        lease.address,
+                        e
+                    );
+                    nonce_pool.invalidate_in_background(lease, self.wallet.clone());
                 }
             }
+        }
+        self.settle_tip(buy.tip, &outcome);
+
+        self.finish_buy(event, &buy, outcome?).await
+    }
+
+    /// Final on-chain status of a transaction that can no longer land, if it already did
+    async fn settled_status(&self, signature: Signature, sent_at: Instant) -> Option<SendOutcome> {
+        let status = match self.rpc_client.get_signature_statuses(&[signature]).await {
+            Ok(response) => response.value.into_iter().next().flatten()?,
             Err(e) => {
-                log::warn!("Failed to check liquidity: {}", e);
-                // Continue anyway - liquidity check is optional
+                log::warn!("Error checking transaction status: {}", e);
+                return None;
             }
+        };
+
+        if let Some(error) = status.err {
+            return Some(SendOutcome::Failed { signature, error });
         }
+        status
+            .satisfies_commitment(self.config.confirmation_commitment)
+            .then(|| {
+                self.landed(
+                    signature,
+                    status.slot,
+                    sent_at.elapsed(),
+                    ConfirmationSource::Polling,
+                    None,
+                )
+            })
+    }
+
+    /// Latest blockhash and last valid block height, from the cache when fresh
+    async fn latest_blockhash(&self) -> Result<(Hash, u64)> {
+        match self.blockhash_cache.get() {
+            Some(latest) => Ok((latest.blockhash, latest.last_valid_block_height)),
+            None => self
+                .rpc_client
+                .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                .await
+                .context("Failed to get latest blockhash"),
+        }
+    }
+
+    /// Build and sign a buy
+    ///
+    /// With a nonce lease, the transaction is signed against the durable nonce
+    /// instead of `blockhash` and advances it first.
+    async fn build_signed_buy(
+        &self,
+        event: &PoolCreationEvent,
+        plan: &BuyPlan,
+        blockhash: Hash,
+        nonce: Option<&NonceLease>,
+    ) -> Result<SignedBuy> {
+        let buy_amount_lamports = utils::sol_to_lamports(plan.amount_sol);
+        let keys = PoolKeys::fetch(&self.rpc_client, &event.pool, event.pool_type).await?;
+        let mint = keys.token_mint();
+
+        // Quote against current reserves for the minimum amount out; without one there is no safe minimum
+        let reserves = Reserves::fetch(&self.rpc_client, &keys)
+            .await
+            .with_context(|| format!("Could not quote buy on {}", event.pool))?;
+        let quoted_tokens = reserves.buy_out(buy_amount_lamports);
+        if quoted_tokens == 0 {
+            anyhow::bail!("Quote for buy on {} is zero tokens", event.pool);
+        }
+        let min_amount_out = utils::calculate_min_amount_out(quoted_tokens, self.config.slippage_bps);
+
+        let swap_ixs = self
+            .swap_instructions(&keys, SwapSide::Buy, buy_amount_lamports, min_amount_out)
+            .await?;
+        // Tip sized from the tip floor and pool score; None skips Jito for this buy
+        let tip = match self.jito_client {
+            Some(_) => self.tip_oracle.choose_tip(plan.score.total),
+            None => None,
+        };
 
-        // Check rug indicators
-        match self.check_rug_indicators(&event.pool).await {
-            Ok(is_safe) => {
-                if !is_safe {
-                    log::warn!("Pool has rug pull indicators: {}", event.pool);
-                    return Ok(false);
+        // Priority fee per CU
+        let priority_fee = self
+            .fee_estimator
+            .estimate(&event.program_id, &event.pool)
+            .await;
+
+        let signed = self
+            .sign_swap(event.pool_type, swap_ixs, tip, priority_fee, blockhash, nonce)
+            .await;
+        if signed.is_err() {
+            self.release_tip(tip);
+        }
+        let (transaction, fee_saved) = signed?;
+
+        Ok(SignedBuy {
+            transaction,
+            tip,
+            mint,
+            quoted_tokens,
+            fee_saved,
+        })
+    }
+
+    /// Add the tip, priority fee and CU limit to a swap's instructions and sign them
+    ///
+    /// Returns the transaction and the priority fee its CU limit saves versus the maximum.
+    async fn sign_swap(
+        &self,
+        pool_type: PoolType,
+        mut instructions: Vec<Instruction>,
+        tip: Option<u64>,
+        priority_fee: u64,
+        blockhash: Hash,
+        nonce: Option<&NonceLease>,
+    ) -> Result<(VersionedTransaction, u64)> {
+        if let Some(tip) = tip {
+            log::info!("Jito tip: {} lamports", tip);
+            instructions.push(jito::tip_instruction(&self.wallet.pubkey(), tip));
+        }
+
+        // Size the CU limit from the learned profile, or by simulating first
+        let mut compute_unit_limit = self.compute_profile.limit_for(pool_type);
+        if self.config.simulate_compute_units {
+            let probe = self.sign_transaction(
+                &instructions,
+                self.config.max_compute_units,
+                priority_fee,
+                blockhash,
+                nonce,
+            )?;
+            match self.simulate_compute_units(&probe).await {
+                Ok(units) => {
+                    self.compute_profile.record(pool_type, units);
+                    compute_unit_limit = self.compute_profile.with_margin(units);
+                }
+                Err(e) => {
+                    log::warn!("Compute unit simulation failed, using profile limit: {}", e);
                 }
-            }
-            Err(e) => {
-                log::warn!("Failed to check rug indicators: {}", e);
-                // Continue anyway
             }
         }
 
-        log::info!("Pool passed all filters: {}", event.pool);
-        Ok(true)
-    }
-
-    /// Check if pool meets liquidity requirements
-    async fn check_liquidity(&self, pool: &Pubkey, pool_type: &PoolType) -> Result<bool> {
-        // Fetch pool account data
-        // Parse to get initial liquidity
-        // Compare against min/max thresholds
-        
-        // Placeholder - implement based on pool account structure
-        // You'll need to:
-        // 1. Fetch pool account data
-        // 2. Deserialize pool account (different for AMM v4 vs CPMM)
-        // 3. Extract token reserves
-        // 4. Calculate USD value
-        // 5. Check against config thresholds
-        
-        // For now, return true (passes check)
-        Ok(true)
-    }
-
-    /// Check rug pull indicators
-    async fn check_rug_indicators(&self, pool: &Pubkey) -> Result<bool> {
-        // Fetch pool account to get token mints
-        // Then check each mint for:
-        // - Mint authority (should be None)
-        // - Freeze authority (should be None)
-        // - LP token supply/burn status
-        
-        // Placeholder - implement actual checks
-        Ok(true)
+        let saved = self.compute_profile.savings(compute_unit_limit, priority_fee);
+        log::info!(
+            "Compute budget: limit {} CUs (max {}), priority fee {} micro-lamports/CU, saves {} lamports if it lands",
+            compute_unit_limit,
+            self.config.max_compute_units,
+            priority_fee,
+            saved
+        );
+
+        let transaction = self.sign_transaction(
+            &instructions,
+            compute_unit_limit,
+            priority_fee,
+            blockhash,
+            nonce,
+        )?;
+        Ok((transaction, saved))
     }
 
-    /// Execute a buy on a pool
-    pub async fn execute_buy(&self, event: &PoolCreationEvent) -> Result<String> {
+    /// Count a landed transaction's priority fee savings
+    fn record_savings(&self, saved: u64) {
+        self.compute_profile.record_savings(saved);
+        log::info!(
+            "Saved {} lamports of priority fees ({} total)",
+            saved,
+            self.compute_profile.total_saved_lamports()
+        );
+    }
+
+    /// Sell `token_amount` of a position's tokens back to SOL
+    pub async fn execute_sell(
+        &self,
+        position: &Position,
+        token_amount: u64,
+        reason: &ExitReason,
+    ) -> Result<SellReceipt> {
+        let pool = Pubkey::from_str(&position.pool).context("Invalid position pool")?;
+        let mint = Pubkey::from_str(&position.mint).context("Invalid position mint")?;
+
+        let keys = PoolKeys::fetch(&self.rpc_client, &pool, position.pool_type).await?;
+        let reserves = Reserves::fetch(&self.rpc_client, &keys).await?;
+        let quoted_lamports = reserves.sell_out(token_amount);
+
         if self.config.dry_run {
             log::info!(
-                "[DRY RUN] Would buy from pool: pool={}, amount={} SOL",
-                event.pool,
-                self.config.buy_amount_sol
+                "[DRY RUN] Would sell {} of {} for ~{:.4} SOL ({})",
+                token_amount,
+                position.mint,
+                quoted_lamports as f64 / 1_000_000_000.0,
+                reason
             );
-            return Ok("dry_run_simulation".to_string());
+            return Ok(SellReceipt {
+                signature: "dry_run_simulation".to_string(),
+                quoted_lamports,
+                fill: None,
+            });
         }
 
         log::info!(
-            "Executing buy: pool={}, amount={} SOL, type={:?}",
-            event.pool,
-            self.config.buy_amount_sol,
-            event.pool_type
+            "Executing sell: {} of {} for ~{:.4} SOL ({})",
+            token_amount,
+            position.mint,
+            quoted_lamports as f64 / 1_000_000_000.0,
+            reason
         );
 
-        // Get latest blockhash
-        let (blockhash, _) = self
-            .rpc_client
-            .get_latest_blockhash()
-            .await
-            .context("Failed to get latest blockhash")?;
-
-        // Build swap instruction based on pool type
-        let buy_amount_lamports = utils::sol_to_lamports(self.config.buy_amount_sol);
-        
-        // Calculate min amount out with slippage
-        // Note: This is simplified - you should calculate based on pool reserves
-        let estimated_amount_out = buy_amount_lamports; // Placeholder
-        let min_amount_out = utils::calculate_min_amount_out(
-            estimated_amount_out,
-            self.config.slippage_bps,
-        );
+        let min_amount_out =
+            utils::calculate_min_amount_out(quoted_lamports, self.config.slippage_bps);
+        let swap_ixs = self
+            .swap_instructions(&keys, SwapSide::Sell, token_amount, min_amount_out)
+            .await?;
 
-        let swap_ix = match event.pool_type {
-            PoolType::AMMv4 => {
-                // Build AMM v4 swap instruction
-                // Note: This requires deriving many accounts - simplified here
-                self.build_amm_v4_swap(&event.pool, buy_amount_lamports, min_amount_out)
-                    .await?
-            }
-            PoolType::CPMM => {
-                // Build CPMM swap instruction
-                self.build_cpmm_swap(&event.pool, buy_amount_lamports, min_amount_out)
-                    .await?
-            }
+        // Emergency exits pay the maximum priority fee and, if enabled, go out as a Jito bundle
+        let urgent = reason.is_emergency();
+        let priority_fee = if urgent {
+            self.config.priority_fee_max_micro_lamports
+        } else {
+            self.fee_estimator
+                .estimate(&pool::program_id(position.pool_type), &pool)
+                .await
+        };
+        let tip = match self.jito_client {
+            Some(_) if urgent && self.config.emergency_use_jito => self.tip_oracle.choose_tip(1.0),
+            _ => None,
         };
 
-        // Build transaction
-        let mut transaction = solana_sdk::transaction::Transaction::new_with_payer(
-            &[swap_ix],
-            Some(&self.wallet.pubkey()),
-        );
+        let signed = match self.latest_blockhash().await {
+            Ok((blockhash, last_valid_block_height)) => self
+                .sign_swap(position.pool_type, swap_ixs, tip, priority_fee, blockhash, None)
+                .await
+                .map(|signed| (signed, last_valid_block_height)),
+            Err(e) => Err(e),
+        };
+        if signed.is_err() {
+            self.release_tip(tip);
+        }
+        let ((transaction, fee_saved), last_valid_block_height) = signed?;
 
-        // Add priority fee instruction
-        let priority_fee = utils::estimate_priority_fee(
+        let outcome = self
+            .send_swap(transaction, tip, last_valid_block_height)
+            .await;
+        self.settle_tip(tip, &outcome);
+        let outcome = outcome?;
+        if !matches!(outcome, SendOutcome::Landed { .. }) {
+            anyhow::bail!("Sell {}", outcome);
+        }
+        log::info!("Sell {}", outcome);
+        self.record_savings(fee_saved);
+
+        let fill = match fills::fetch_fill(
             &self.rpc_client,
-            self.config.priority_fee_micro_lamports,
+            outcome.signature(),
+            &self.wallet.pubkey(),
+            &mint,
+            tip.unwrap_or(0),
+            None,
         )
-        .await;
-
-        let priority_fee_ix = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
-        let compute_unit_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(
-            self.config.max_compute_units,
-        );
-
-        transaction.instructions.insert(0, priority_fee_ix);
-        transaction.instructions.insert(1, compute_unit_limit_ix);
+        .await
+        {
+            Ok(fill) => Some(fill),
+            Err(e) => {
+                log::warn!("Could not parse fill for {}: {}", outcome.signature(), e);
+                None
+            }
+        };
 
-        transaction.sign(&[self.wallet.keypair()], blockhash);
+        Ok(SellReceipt {
+            signature: outcome.signature().to_string(),
+            quoted_lamports,
+            fill,
+        })
+    }
 
-        // Convert to VersionedTransaction
-        let versioned_tx = VersionedTransaction::from(transaction);
+    /// Return a reserved tip to the hourly budget
+    fn release_tip(&self, tip: Option<u64>) {
+        if let Some(tip) = tip {
+            self.tip_oracle.release(tip);
+        }
+    }
 
-        // Send with retry
-        self.send_transaction_with_retry(versioned_tx, 3).await
+    /// Keep a reserved tip only if its transaction landed; a failed, expired or
+    /// unsent transaction pays no tip
+    fn settle_tip(&self, tip: Option<u64>, outcome: &Result<SendOutcome>) {
+        if !matches!(outcome, Ok(SendOutcome::Landed { .. })) {
+            self.release_tip(tip);
+        }
     }
 
-    /// Build AMM v4 swap instruction
-    async fn build_amm_v4_swap(
+    /// Send as a Jito bundle if a tip was reserved, otherwise (or on rejection) via RPC
+    async fn send_swap(
         &self,
-        _pool: &Pubkey,
-        amount_in: u64,
-        min_amount_out: u64,
-    ) -> Result<solana_sdk::instruction::Instruction> {
-        // This is a placeholder - actual implementation requires:
-        // 1. Fetching pool account to get all required accounts
-        // 2. Deriving associated token accounts
-        // 3. Getting AMM, authority, open orders, etc.
-        // 4. Building the full instruction with all accounts
-        
-        // For now, return a simplified version
-        anyhow::bail!("AMM v4 swap instruction building not fully implemented - requires pool account parsing");
-    }
-
-    /// Build CPMM swap instruction
-    async fn build_cpmm_swap(
+        transaction: VersionedTransaction,
+        tip: Option<u64>,
+        last_valid_block_height: u64,
+    ) -> Result<SendOutcome> {
+        match (&self.jito_client, tip) {
+            (Some(jito), Some(tip)) => {
+                self.send_via_jito(jito, transaction, tip, last_valid_block_height)
+                    .await
+            }
+            _ => self.send_until_expiry(&transaction, last_valid_block_height).await,
+        }
+    }
+
+    /// Turn a send outcome into a receipt and learn from the landed transaction
+    async fn finish_buy(
         &self,
-        pool: &Pubkey,
-        amount_in: u64,
-        min_amount_out: u64,
-    ) -> Result<solana_sdk::instruction::Instruction> {
-        // Fetch pool account to get token accounts
-        // This is simplified - actual implementation requires parsing pool account
-        
-        // Placeholder accounts
-        let user_source = self.wallet.pubkey();
-        let user_dest = self.wallet.pubkey();
-        let pool_source = *pool; // Placeholder
-        let pool_dest = *pool; // Placeholder
-
-        build_cpmm_swap_instruction(
+        event: &PoolCreationEvent,
+        buy: &SignedBuy,
+        outcome: SendOutcome,
+    ) -> Result<BuyReceipt> {
+        let mut receipt = match outcome {
+            SendOutcome::Landed {
+                signature,
+                latency,
+                source,
+                ..
+            } => {
+                log::info!("Buy {}", outcome);
+                self.record_savings(buy.fee_saved);
+                BuyReceipt {
+                    signature: signature.to_string(),
+                    confirmation: Some((latency, source)),
+                    fill: None,
+                }
+            }
+            _ => anyhow::bail!("Buy {}", outcome),
+        };
+
+        // Parse what the buy actually received and paid
+        match fills::fetch_fill(
+            &self.rpc_client,
+            outcome.signature(),
             &self.wallet.pubkey(),
-            pool,
-            &user_source,
-            &user_dest,
-            &pool_source,
-            &pool_dest,
-            amount_in,
-            min_amount_out,
+            &buy.mint,
+            buy.tip.unwrap_or(0),
+            Some(buy.quoted_tokens),
         )
+        .await
+        {
+            Ok(fill) => {
+                log::info!(
+                    "Filled {} tokens for {} lamports (fee {}, tip {}), price {:.10} SOL, slippage {} bps",
+                    fill.tokens_received(),
+                    fill.sol_spent_lamports,
+                    fill.fee_lamports,
+                    fill.tip_lamports,
+                    fill.effective_price,
+                    fill.slippage_bps.map_or("-".to_string(), |bps| bps.to_string())
+                );
+                receipt.fill = Some(fill);
+            }
+            Err(e) => log::warn!("Could not parse fill for {}: {}", receipt.signature, e),
+        }
+
+        // Learn from the CUs the landed transaction actually used
+        if let Err(e) = self
+            .record_landed_compute_units(&receipt.signature, event.pool_type)
+            .await
+        {
+            log::debug!("Could not read compute units for {}: {}", receipt.signature, e);
+        }
+
+        Ok(receipt)
     }
 
-    /// Send transaction with retry logic
-    async fn send_transaction_with_retry(
+    /// Submit a transaction as a single-transaction bundle, falling back to RPC
+    ///
+    /// The fallback re-sends the same signed transaction (tip included), so the
+    /// buy can land at most once even if the bundle lands late.
+    async fn send_via_jito(
         &self,
+        jito: &JitoClient,
         transaction: VersionedTransaction,
-        max_retries: u32,
-    ) -> Result<String> {
-        let mut last_error = None;
-
-        for attempt in 1..=max_retries {
-            log::info!("Sending buy transaction (attempt {}/{})", attempt, max_retries);
-
-            match self.rpc_client.send_transaction(&transaction).await {
-                Ok(signature) => {
-                    log::info!("Buy transaction sent: {}", signature);
-                    
-                    // Wait for confirmation
-                    if let Err(e) = self.wait_for_confirmation(&signature).await {
-                        log::warn!("Transaction sent but confirmation error: {}", e);
-                    }
+        tip: u64,
+        last_valid_block_height: u64,
+    ) -> Result<SendOutcome> {
+        let signature = transaction.signatures[0];
+        let start = Instant::now();
 
-                    return Ok(signature.to_string());
-                }
-                Err(e) => {
-                    log::warn!("Transaction send failed (attempt {}): {}", attempt, e);
-                    last_error = Some(e);
+        match jito.send_bundle(std::slice::from_ref(&transaction)).await {
+            Ok(bundle_id) => {
+                log::info!("Bundle submitted: {} (tx {})", bundle_id, signature);
+                let timeout = Duration::from_millis(self.config.jito_bundle_timeout_ms);
 
-                    if attempt < max_retries {
-                        let delay = Duration::from_millis(1000 * attempt as u64);
-                        log::info!("Retrying in {:?}...", delay);
-                        sleep(delay).await;
+                match jito.wait_for_bundle(&bundle_id, timeout).await {
+                    Ok(BundleStatus::Landed { slot }) => {
+                        log::info!("Bundle {} landed in slot {}", bundle_id, slot);
+                        self.tip_oracle.record_landed(tip);
+                        return Ok(SendOutcome::Landed {
+                            signature,
+                            slot,
+                            latency: start.elapsed(),
+                            source: ConfirmationSource::Bundle,
+                        });
+                    }
+                    Ok(status) => {
+                        log::warn!("Bundle {} did not land ({:?}), falling back to RPC", bundle_id, status);
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to poll bundle {}: {}, falling back to RPC", bundle_id, e);
                     }
                 }
             }
+            Err(e) => {
+                log::warn!("Bundle submission failed: {}, falling back to RPC", e);
+            }
+        }
+
+        self.send_until_expiry(&transaction, last_valid_block_height)
+            .await
+    }
+
+    /// Prepend compute budget (and nonce advance) instructions and sign a v0 transaction
+    ///
+    /// Accounts held by the bot's lookup table (if loaded) are referenced by index.
+    fn sign_transaction(
+        &self,
+        instructions: &[Instruction],
+        compute_unit_limit: u32,
+        priority_fee: u64,
+        blockhash: Hash,
+        nonce: Option<&NonceLease>,
+    ) -> Result<VersionedTransaction> {
+        // A durable nonce transaction must advance the nonce first and sign against it
+        let (mut all_instructions, blockhash) = match nonce {
+            Some(lease) => (vec![lease.advance_instruction()], lease.nonce),
+            None => (vec![], blockhash),
+        };
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee));
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit));
+        all_instructions.extend_from_slice(instructions);
+
+        let message = v0::Message::try_compile(
+            &self.wallet.pubkey(),
+            &all_instructions,
+            self.lookup_table.as_slice(),
+            blockhash,
+        )
+        .context("Failed to compile transaction message")?;
+
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[self.wallet.keypair()])
+            .context("Failed to sign transaction")
+    }
+
+    /// Simulate a transaction and return the compute units it consumed
+    async fn simulate_compute_units(&self, transaction: &VersionedTransaction) -> Result<u32> {
+        let result = self
+            .rpc_client
+            .simulate_transaction_with_config(
+                transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .context("Failed to simulate transaction")?
+            .value;
+
+        if let Some(err) = result.err {
+            anyhow::bail!("Simulation failed: {:?}", err);
         }
 
-        Err(last_error
-            .unwrap_or_else(|| anyhow::anyhow!("Transaction failed after {} retries", max_retries))
-            .into())
+        let units = result
+            .units_consumed
+            .context("Simulation did not report consumed compute units")?;
+
+        Ok(units as u32)
+    }
+
+    /// Fetch a confirmed transaction and record its consumed compute units
+    async fn record_landed_compute_units(&self, signature: &str, pool_type: PoolType) -> Result<()> {
+        let signature = Signature::from_str(signature).context("Invalid signature")?;
+        let tx = self
+            .rpc_client
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .context("Failed to fetch transaction")?;
+
+        let units: Option<u64> = tx
+            .transaction
+            .meta
+            .context("Transaction has no status meta")?
+            .compute_units_consumed
+            .into();
+        let units = units.context("Transaction meta has no compute units")?;
+
+        self.compute_profile.record(pool_type, units as u32);
+        Ok(())
+    }
+
+    /// Instructions swapping between SOL and the pool's token through the wallet's ATAs
+    ///
+    /// Buys wrap `amount_in` lamports into the WSOL account first and create the
+    /// token account if needed. Both sides close the WSOL account afterwards, so
+    /// the SOL side always ends up unwrapped in the wallet.
+    async fn swap_instructions(
+        &self,
+        keys: &PoolKeys,
+        side: SwapSide,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<Vec<Instruction>> {
+        let wallet = self.wallet.pubkey();
+        let mint = keys.token_mint();
+        let token_program = keys.token_program(&mint);
+        let wsol_account =
+            get_associated_token_address_with_program_id(&wallet, &WSOL_MINT, &spl_token::id());
+        let token_account = get_associated_token_address_with_program_id(&wallet, &mint, &token_program);
+
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &wallet,
+            &wallet,
+            &WSOL_MINT,
+            &spl_token::id(),
+        )];
+        let (source, dest, input_mint) = match side {
+            SwapSide::Buy => {
+                instructions.push(system_instruction::transfer(&wallet, &wsol_account, amount_in));
+                instructions.push(
+                    spl_token::instruction::sync_native(&spl_token::id(), &wsol_account)
+                        .context("Failed to build sync native instruction")?,
+                );
+                instructions.push(create_associated_token_account_idempotent(
+                    &wallet,
+                    &wallet,
+                    &mint,
+                    &token_program,
+                ));
+                (wsol_account, token_account, WSOL_MINT)
+            }
+            SwapSide::Sell => (token_account, wsol_account, mint),
+        };
+
+        let swap_ix = match keys.pool_type {
+            PoolType::AMMv4 => {
+                let pool::SwapAccounts::AmmV4 {
+                    market,
+                    market_program,
+                    ..
+                } = keys.swap_accounts
+                else {
+                    anyhow::bail!("Pool {} has no AMM v4 accounts", keys.pool);
+                };
+                let market_keys = MarketKeys::fetch(&self.rpc_client, &market, &market_program).await?;
+                build_amm_v4_swap_instruction(
+                    keys,
+                    &market_keys,
+                    &source,
+                    &dest,
+                    &wallet,
+                    amount_in,
+                    min_amount_out,
+                )?
+            }
+            PoolType::CPMM => build_cpmm_swap_instruction(
+                keys,
+                &wallet,
+                &input_mint,
+                &source,
+                &dest,
+                amount_in,
+                min_amount_out,
+            )?,
+        };
+        instructions.push(swap_ix);
+
+        instructions.push(
+            spl_token::instruction::close_account(
+                &spl_token::id(),
+                &wsol_account,
+                &wallet,
+                &wallet,
+                &[],
+            )
+            .context("Failed to build close account instruction")?,
+        );
+
+        Ok(instructions)
     }
 
-    /// Wait for transaction confirmation
-    async fn wait_for_confirmation(
+    /// Re-broadcast a signed transaction until it reaches the target commitment
+    /// or its blockhash expires
+    ///
+    /// Every round re-sends the same signed transaction (via the TPU sender if
+    /// enabled, and every healthy broadcast endpoint), so at most one copy can
+    /// land. Re-sending stops while the transaction is visible on-chain and
+    /// resumes if it disappears again (e.g. its fork was dropped).
+    async fn send_until_expiry(
         &self,
-        signature: &solana_sdk::signature::Signature,
-    ) -> Result<()> {
-        const MAX_WAIT_TIME: Duration = Duration::from_secs(30);
-        const POLL_INTERVAL: Duration = Duration::from_millis(500);
-        let start = std::time::Instant::now();
+        transaction: &VersionedTransaction,
+        last_valid_block_height: u64,
+    ) -> Result<SendOutcome> {
+        let signature = transaction.signatures[0];
+        let target = self.config.confirmation_commitment;
+        let interval = Duration::from_millis(self.config.rebroadcast_interval_ms);
+        let start = Instant::now();
+
+        let mut first_broadcast: Option<BroadcastResult> = None;
+        let mut status: Option<TransactionStatus> = None;
+        let mut rounds = 0u32;
+        let mut ticks = 0u32;
+
+        // Confirmations are pushed; polling is the fallback and a safety net.
+        // Status is always polled on the first round, and on every round while
+        // the signature stream is down.
+        let mut notice = self.signature_watcher.watch(signature);
+        let mut watching = true;
 
         loop {
-            if start.elapsed() > MAX_WAIT_TIME {
-                anyhow::bail!("Transaction confirmation timeout");
+            if status.is_none() {
+                rounds += 1;
+                self.rebroadcast(transaction, &mut first_broadcast).await;
             }
+            ticks += 1;
 
-            match self.rpc_client.get_signature_status(signature).await {
-                Ok(Some(status)) => {
-                    if status.err.is_some() {
-                        anyhow::bail!("Transaction failed: {:?}", status.err);
-                    }
-                    if status.confirmation_status.is_some() {
-                        log::info!("Transaction confirmed: {}", signature);
-                        return Ok(());
+            if watching {
+                tokio::select! {
+                    received = &mut notice => {
+                        watching = false;
+                        match received {
+                            Ok(SignatureNotice { err: Some(error), .. }) => {
+                                return Ok(SendOutcome::Failed { signature, error });
+                            }
+                            Ok(received) => {
+                                return Ok(self.landed(
+                                    signature,
+                                    received.slot,
+                                    start.elapsed(),
+                                    received.source,
+                                    first_broadcast.as_ref(),
+                                ));
+                            }
+                            Err(_) => {
+                                log::debug!("No pushed confirmations for {}, polling", signature);
+                            }
+                        }
                     }
+                    _ = sleep(interval) => {}
                 }
-                Ok(None) => {
-                    // Still processing
+
+                if watching
+                    && ticks > 1
+                    && ticks % POLL_FALLBACK_TICKS != 0
+                    && self.signature_watcher.is_live()
+                {
+                    continue;
                 }
-                Err(e) => {
-                    log::warn!("Error checking transaction status: {}", e);
+            } else {
+                sleep(interval).await;
+            }
+
+            match self.rpc_client.get_signature_statuses(&[signature]).await {
+                Ok(response) => status = response.value.into_iter().next().flatten(),
+                Err(e) => log::warn!("Error checking transaction status: {}", e),
+            }
+
+            if let Some(ref current) = status {
+                if let Some(ref error) = current.err {
+                    return Ok(SendOutcome::Failed {
+                        signature,
+                        error: error.clone(),
+                    });
+                }
+                if current.satisfies_commitment(target) {
+                    log::debug!("{} reached {:?} after {} send rounds", signature, target.commitment, rounds);
+                    return Ok(self.landed(
+                        signature,
+                        current.slot,
+                        start.elapsed(),
+                        ConfirmationSource::Polling,
+                        first_broadcast.as_ref(),
+                    ));
                 }
+                continue;
             }
 
-            sleep(POLL_INTERVAL).await;
+            // Not seen on-chain: give up once the blockhash can no longer be included
+            match self
+                .rpc_client
+                .get_block_height_with_commitment(CommitmentConfig::confirmed())
+                .await
+            {
+                Ok(height) if height > last_valid_block_height => {
+                    return Ok(SendOutcome::Expired { signature });
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Error checking block height: {}", e),
+            }
+        }
+    }
+
+    /// Credit the send endpoints and build the landed outcome
+    fn landed(
+        &self,
+        signature: Signature,
+        slot: u64,
+        latency: Duration,
+        source: ConfirmationSource,
+        first_broadcast: Option<&BroadcastResult>,
+    ) -> SendOutcome {
+        if let Some(result) = first_broadcast {
+            self.broadcaster.record_landed(result);
+            log::info!("Send endpoint stats:\n{}", self.broadcaster);
+        }
+
+        SendOutcome::Landed {
+            signature,
+            slot,
+            latency,
+            source,
+        }
+    }
+
+    /// One send round; failures are logged and retried on the next round
+    async fn rebroadcast(
+        &self,
+        transaction: &VersionedTransaction,
+        first_broadcast: &mut Option<BroadcastResult>,
+    ) {
+        if let Some(ref tpu) = self.tpu_sender {
+            if let Err(e) = tpu.send(transaction).await {
+                log::debug!("{}", e);
+            }
+        }
+
+        match self.broadcaster.broadcast(transaction).await {
+            Ok(result) => {
+                if first_broadcast.is_none() {
+                    log::info!(
+                        "Buy transaction sent: {} (accepted by {}/{} endpoints, first: {})",
+                        result.signature,
+                        result.accepted.len(),
+                        self.broadcaster.stats().len(),
+                        result.first().unwrap_or("-")
+                    );
+                    *first_broadcast = Some(result);
+                }
+            }
+            Err(e) => log::debug!("Broadcast of {} failed: {}", transaction.signatures[0], e),
         }
     }
 
@@ -328,3 +1135,17 @@ impl Sniper {
         Ok(balance)
     }
 }
+
+/// Parse a list of addresses, skipping (and logging) invalid entries
+fn parse_pubkey_set(addresses: &[String], label: &str) -> HashSet<Pubkey> {
+    addresses
+        .iter()
+        .filter_map(|s| match Pubkey::from_str(s) {
+            Ok(pubkey) => Some(pubkey),
+            Err(e) => {
+                log::warn!("Ignoring invalid blacklisted {} {}: {}", label, s, e);
+                None
+            }
+        })
+        .collect()
+}
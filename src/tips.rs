@@ -0,0 +1,211 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::config::Config;
+use crate::fees;
+use crate::utils;
+
+/// Default tip floor endpoint
+pub const DEFAULT_TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+
+/// Minimum tip the block engine accepts
+const MIN_TIP_LAMPORTS: u64 = 1_000;
+
+/// Landed tips kept for the local percentile fallback
+const MAX_HISTORY: usize = 200;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Landed-tip percentiles published by the block engine, in lamports
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TipFloor {
+    pub p25: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+impl TipFloor {
+    /// Parse the tip floor API response (values are in SOL)
+    fn from_json(value: &Value) -> Option<Self> {
+        let entry = value.get(0)?;
+        let field = |name: &str| entry.get(name)?.as_f64().map(utils::sol_to_lamports);
+
+        Some(Self {
+            p25: field("landed_tips_25th_percentile")?,
+            p50: field("landed_tips_50th_percentile")?,
+            p75: field("landed_tips_75th_percentile")?,
+            p95: field("landed_tips_95th_percentile")?,
+            p99: field("landed_tips_99th_percentile")?,
+        })
+    }
+
+    /// Nearest published percentile at or above `pct`
+    fn at(&self, pct: u8) -> u64 {
+        match pct {
+            0..=25 => self.p25,
+            26..=50 => self.p50,
+            51..=75 => self.p75,
+            76..=95 => self.p95,
+            _ => self.p99,
+        }
+    }
+}
+
+/// Chooses Jito tips from tip-floor statistics, scaled by pool score and capped per hour
+///
+/// The tip floor is polled from the block engine; if it's unavailable, percentiles
+/// of our own landed tips are used, and failing that the static `jito_tip_lamports`.
+pub struct TipOracle {
+    http: reqwest::Client,
+    floor_url: String,
+    static_tip: u64,
+    percentile: u8,
+    max_score_multiplier: f64,
+    min_tip: u64,
+    max_tip: u64,
+    hourly_cap: Option<u64>,
+    floor: RwLock<Option<TipFloor>>,
+    landed: Mutex<VecDeque<u64>>,
+    spent: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl TipOracle {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            floor_url: config
+                .jito_tip_floor_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TIP_FLOOR_URL.to_string()),
+            static_tip: config.jito_tip_lamports,
+            percentile: config.jito_tip_percentile.min(100),
+            max_score_multiplier: config.jito_tip_score_multiplier,
+            min_tip: config.jito_tip_min_lamports.max(MIN_TIP_LAMPORTS),
+            max_tip: config.jito_tip_max_lamports,
+            hourly_cap: config.jito_tips_per_hour_cap_lamports,
+            floor: RwLock::new(None),
+            landed: Mutex::new(VecDeque::new()),
+            spent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    async fn fetch_floor(&self) -> Result<TipFloor> {
+        let value: Value = self
+            .http
+            .get(&self.floor_url)
+            .send()
+            .await
+            .context("Failed to fetch tip floor")?
+            .json()
+            .await
+            .context("Invalid tip floor response")?;
+
+        TipFloor::from_json(&value).context("Unexpected tip floor format")
+    }
+
+    /// Poll the tip floor endpoint
+    pub async fn run_refresh(self: Arc<Self>, interval: Duration) {
+        loop {
+            match self.fetch_floor().await {
+                Ok(floor) => {
+                    log::debug!("Jito tip floor: {:?}", floor);
+                    *self.floor.write().unwrap() = Some(floor);
+                }
+                Err(e) => log::warn!("{}", e),
+            }
+            sleep(interval).await;
+        }
+    }
+
+    /// Record the tip of a bundle that landed
+    pub fn record_landed(&self, tip: u64) {
+        let mut landed = self.landed.lock().unwrap();
+        landed.push_back(tip);
+        while landed.len() > MAX_HISTORY {
+            landed.pop_front();
+        }
+    }
+
+    /// Base tip before score scaling
+    fn base_tip(&self) -> u64 {
+        if let Some(floor) = *self.floor.read().unwrap() {
+            return floor.at(self.percentile);
+        }
+
+        let tips: Vec<u64> = self.landed.lock().unwrap().iter().copied().collect();
+        fees::percentile(tips, self.percentile).unwrap_or(self.static_tip)
+    }
+
+    /// Choose and reserve a tip for a pool with the given score (0.0 - 1.0)
+    ///
+    /// The reservation counts against the hourly cap until `release`d; the send
+    /// path releases it unless the transaction carrying the tip lands.
+    /// Returns `None` if the hourly tip budget is exhausted.
+    pub fn choose_tip(&self, score: f64) -> Option<u64> {
+        let multiplier = 1.0 + (self.max_score_multiplier - 1.0) * score.clamp(0.0, 1.0);
+        let mut tip = ((self.base_tip() as f64 * multiplier) as u64)
+            .clamp(self.min_tip, self.max_tip.max(self.min_tip));
+
+        let mut spent = self.spent.lock().unwrap();
+        while spent.front().is_some_and(|(at, _)| at.elapsed() > HOUR) {
+            spent.pop_front();
+        }
+
+        if let Some(cap) = self.hourly_cap {
+            let used: u64 = spent.iter().map(|(_, tip)| tip).sum();
+            let remaining = cap.saturating_sub(used);
+            if remaining < self.min_tip {
+                log::warn!("Hourly Jito tip cap reached ({} lamports)", cap);
+                return None;
+            }
+            tip = tip.min(remaining);
+        }
+
+        spent.push_back((Instant::now(), tip));
+        Some(tip)
+    }
+    /// Return a reserved tip to the hourly budget, for a transaction that never landed
+    pub fn release(&self, tip: u64) {
+        let mut spent = self.spent.lock().unwrap();
+        if let Some(index) = spent.iter().rposition(|(_, reserved)| *reserved == tip) {
+            spent.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle(cap: u64) -> TipOracle {
+        let mut config = Config::default();
+        config.jito_tip_lamports = 10_000;
+        config.jito_tip_min_lamports = 1_000;
+        config.jito_tip_max_lamports = 10_000;
+        config.jito_tip_score_multiplier = 1.0;
+        config.jito_tips_per_hour_cap_lamports = Some(cap);
+        TipOracle::new(&config)
+    }
+
+    #[test]
+    fn reserved_tips_count_against_the_cap() {
+        let oracle = oracle(15_000);
+        assert_eq!(oracle.choose_tip(0.5), Some(10_000));
+        assert_eq!(oracle.choose_tip(0.5), Some(5_000));
+        assert_eq!(oracle.choose_tip(0.5), None);
+    }
+
+    #[test]
+    fn released_tips_return_to_the_budget() {
+        let oracle = oracle(15_000);
+        let tip = oracle.choose_tip(0.5).unwrap();
+        oracle.release(tip);
+        assert_eq!(oracle.choose_tip(0.5), Some(10_000));
+        assert_eq!(oracle.choose_tip(0.5), Some(5_000));
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use solana_account_decoder::UiAccountData;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_client::rpc_response::RpcKeyedAccount;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::pool::{TOKEN_2022_PROGRAM_ID, WSOL_MINT};
+use crate::wallet::Wallet;
+
+/// Accounts closed per transaction (keeps the transaction well under the size limit)
+const CLOSE_CHUNK: usize = 20;
+
+/// A wallet token account that can be closed to reclaim its rent
+#[derive(Debug, Clone)]
+pub struct ClosableAccount {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    /// Token program owning the account (SPL Token or Token-2022)
+    pub program_id: Pubkey,
+    /// Raw token amount (non-zero only for WSOL, which closing unwraps)
+    pub amount: u64,
+    /// Lamports returned to the wallet on close, rent and unwrapped SOL included
+    pub lamports: u64,
+}
+
+/// The wallet's SPL Token and Token-2022 accounts holding no tokens, plus all of its WSOL accounts
+///
+/// Frozen accounts, accounts whose close authority is someone else and
+/// Token-2022 accounts still holding withheld transfer fees are skipped.
+pub async fn closable_accounts(rpc_client: &RpcClient, wallet: &Pubkey) -> Result<Vec<ClosableAccount>> {
+    let (legacy, token_2022) = tokio::join!(
+        rpc_client.get_token_accounts_by_owner(wallet, TokenAccountsFilter::ProgramId(spl_token::id())),
+        rpc_client.get_token_accounts_by_owner(wallet, TokenAccountsFilter::ProgramId(TOKEN_2022_PROGRAM_ID)),
+    );
+
+    let mut closable = Vec::new();
+    collect_closable(
+        legacy.context("Failed to fetch token accounts")?,
+        spl_token::id(),
+        wallet,
+        &mut closable,
+    )?;
+    collect_closable(
+        token_2022.context("Failed to fetch Token-2022 accounts")?,
+        TOKEN_2022_PROGRAM_ID,
+        wallet,
+        &mut closable,
+    )?;
+
+    Ok(closable)
+}
+
+/// Add the closable accounts among `accounts`, all owned by `program_id`
+fn collect_closable(
+    accounts: Vec<RpcKeyedAccount>,
+    program_id: Pubkey,
+    wallet: &Pubkey,
+    closable: &mut Vec<ClosableAccount>,
+) -> Result<()> {
+    for keyed in accounts {
+        let UiAccountData::Json(parsed) = keyed.account.data else {
+            anyhow::bail!("Token account {} was not returned parsed", keyed.pubkey);
+        };
+        let info = &parsed.parsed["info"];
+
+        let mint = info["mint"]
+            .as_str()
+            .and_then(|m| Pubkey::from_str(m).ok())
+            .with_context(|| format!("Token account {} has no mint", keyed.pubkey))?;
+        let amount = info["tokenAmount"]["amount"]
+            .as_str()
+            .and_then(|a| a.parse::<u64>().ok())
+            .with_context(|| format!("Token account {} has no amount", keyed.pubkey))?;
+        let frozen = info["state"].as_str() == Some("frozen");
+        let foreign_close_authority = info["closeAuthority"]
+            .as_str()
+            .is_some_and(|authority| authority != wallet.to_string());
+        // Token-2022 refuses to close an account until its withheld fees are harvested
+        let withheld_fees = info["extensions"].as_array().is_some_and(|extensions| {
+            extensions.iter().any(|extension| {
+                extension["extension"].as_str() == Some("transferFeeAmount")
+                    && extension["state"]["withheldAmount"].as_u64().unwrap_or(0) > 0
+            })
+        });
+
+        if (amount == 0 || mint == WSOL_MINT) && !frozen && !foreign_close_authority && !withheld_fees {
+            closable.push(ClosableAccount {
+                address: Pubkey::from_str(&keyed.pubkey).context("Invalid token account address")?,
+                mint,
+                program_id,
+                amount,
+                lamports: keyed.account.lamports,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Close instruction for an account, addressed to the program that owns it
+fn close_account_instruction(account: &ClosableAccount, wallet: &Pubkey) -> Result<Instruction> {
+    // Token-2022 shares SPL Token's CloseAccount layout, but spl_token only
+    // builds instructions for its own program ID
+    let mut instruction =
+        spl_token::instruction::close_account(&spl_token::id(), &account.address, wallet, wallet, &[])
+            .context("Failed to build close account instruction")?;
+    instruction.program_id = account.program_id;
+    Ok(instruction)
+}
+
+/// Close token accounts, sending their lamports to the wallet
+pub async fn close_accounts(
+    rpc_client: &RpcClient,
+    wallet: &Wallet,
+    accounts: &[ClosableAccount],
+) -> Result<Vec<Signature>> {
+    let mut signatures = Vec::new();
+
+    for chunk in accounts.chunks(CLOSE_CHUNK) {
+        let instructions = chunk
+            .iter()
+            .map(|account| close_account_instruction(account, &wallet.pubkey()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let blockhash = rpc_client
+            .get_latest_blockhash()
+            .await
+            .context("Failed to get latest blockhash")?;
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&wallet.pubkey()),
+            &[wallet.keypair()],
+            blockhash,
+        );
+
+        let signature = rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .await
+            .context("Failed to close token accounts")?;
+        log::info!("Closed {} token accounts ({})", chunk.len(), signature);
+        signatures.push(signature);
+    }
+
+    Ok(signatures)
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use solana_client::{
+    nonblocking::{rpc_client::RpcClient, tpu_client::TpuClient},
+    tpu_client::TpuClientConfig,
+};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
+
+/// Consecutive slots assigned to each leader
+const SLOTS_PER_LEADER: u64 = 4;
+
+/// Sends transactions straight to leader TPU ports over QUIC
+///
+/// The underlying `TpuClient` follows the leader schedule via a slot subscription
+/// and `getClusterNodes` contact info, and keeps a QUIC connection cache so
+/// repeat sends to the same leader reuse connections.
+pub struct TpuSender {
+    client: TpuClient<QuicPool, QuicConnectionManager, QuicConfig>,
+}
+
+impl TpuSender {
+    /// Connect to the cluster; `leaders` is the current leader plus how many upcoming ones to target
+    pub async fn new(rpc_client: Arc<RpcClient>, websocket_url: &str, leaders: u64) -> Result<Self> {
+        let leaders = leaders.max(1);
+        let client = TpuClient::new(
+            "raydium-sniper-tpu",
+            rpc_client,
+            websocket_url,
+            TpuClientConfig {
+                fanout_slots: leaders * SLOTS_PER_LEADER,
+            },
+        )
+        .await
+        .context("Failed to start TPU client")?;
+
+        Ok(Self { client })
+    }
+
+    /// Send a signed transaction to the current and upcoming leaders
+    pub async fn send(&self, transaction: &VersionedTransaction) -> Result<()> {
+        let wire = bincode::serialize(transaction).context("Failed to serialize transaction")?;
+        self.client
+            .try_send_wire_transaction(wire)
+            .await
+            .context("TPU send failed")
+    }
+}
+
+/// Derive the PubSub URL from an RPC URL (http -> ws, and port 8899 -> 8900 for local validators)
+pub fn websocket_url_for(rpc_url: &str) -> String {
+    let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    };
+
+    url.replace(":8899", ":8900")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        commitment_config::CommitmentConfig, native_token::LAMPORTS_PER_SOL, signature::Keypair,
+        signer::Signer, system_transaction,
+    };
+    use tokio::time::{sleep, Duration};
+
+    const LOCAL_RPC_URL: &str = "http://127.0.0.1:8899";
+
+    /// Needs a local `solana-test-validator`; run with `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn sends_to_local_test_validator() {
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(
+            LOCAL_RPC_URL.to_string(),
+            CommitmentConfig::confirmed(),
+        ));
+        let payer = Keypair::new();
+        let airdrop = rpc_client
+            .request_airdrop(&payer.pubkey(), LAMPORTS_PER_SOL)
+            .await
+            .expect("airdrop requested");
+        for _ in 0..60 {
+            if rpc_client.confirm_transaction(&airdrop).await.unwrap_or(false) {
+                break;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+
+        let sender = TpuSender::new(rpc_client.clone(), &websocket_url_for(LOCAL_RPC_URL), 1)
+            .await
+            .expect("TPU client connects");
+        let blockhash = rpc_client.get_latest_blockhash().await.unwrap();
+        let transfer = system_transaction::transfer(&payer, &Keypair::new().pubkey(), 1_000_000, blockhash);
+        let signature = transfer.signatures[0];
+        let transaction = VersionedTransaction::from(transfer);
+
+        for _ in 0..60 {
+            sender.send(&transaction).await.expect("sent over QUIC");
+            if rpc_client.confirm_transaction(&signature).await.unwrap_or(false) {
+                return;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+        panic!("{} did not land via TPU", signature);
+    }
+
+    #[test]
+    fn websocket_url_follows_rpc_url() {
+        assert_eq!(websocket_url_for("https://rpc.example.com"), "wss://rpc.example.com");
+        assert_eq!(websocket_url_for(LOCAL_RPC_URL), "ws://127.0.0.1:8900");
+    }
+}
@@ -0,0 +1,633 @@
+use anyhow::{Context, Result};
+use futures::{stream::select_all, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{RpcAccountInfoConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey, signature::Signature,
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, watch, Notify};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::config::{Config, RAYDIUM_CPMM_PROGRAM_ID};
+use crate::detector::PoolType;
+use crate::instructions;
+use crate::pool::{self, PoolKeys};
+use crate::quote::Reserves;
+use crate::tpu;
+
+/// Reserves, spot price and mint authorities of a pool as of a slot
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSnapshot {
+    pub slot: u64,
+    pub reserves: Reserves,
+    /// SOL per whole token at these reserves, before fees
+    pub price: f64,
+    pub mint_authority: COption<Pubkey>,
+    pub freeze_authority: COption<Pubkey>,
+    /// LP tokens in circulation
+    pub lp_supply: u64,
+    /// When the tracker last heard about the pool, from its stream or over RPC
+    pub updated_at: Instant,
+}
+
+/// A successful transaction that removed liquidity from a tracked pool
+#[derive(Debug, Clone)]
+pub struct Withdrawal {
+    pub pool: Pubkey,
+    pub signature: String,
+    /// LP tokens burned, when the instruction itself was seen rather than its logs
+    pub lp_amount: Option<u64>,
+}
+
+/// Something seen on a PubSub subscription
+enum Notice {
+    Account { address: Pubkey, slot: u64, data: Vec<u8> },
+    Withdrawal { pool: Pubkey, signature: String },
+}
+
+/// Whether transaction logs show the CPMM program itself running its withdraw instruction
+///
+/// Anchor programs all log `Instruction: <name>`, so the line only counts
+/// while the CPMM program is the innermost program invoked.
+fn cpmm_withdraw_logged(logs: &[String]) -> bool {
+    let mut programs: Vec<&str> = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(message) = rest.strip_prefix("log: ") {
+            if message == WITHDRAW_LOG && programs.last() == Some(&RAYDIUM_CPMM_PROGRAM_ID) {
+                return true;
+            }
+        } else if let Some((program, _)) = rest.split_once(" invoke [") {
+            programs.push(program);
+        } else if rest.ends_with(" success") || rest.contains(" failed") {
+            programs.pop();
+        }
+    }
+    false
+}
+
+/// Latest raw account state of a tracked pool
+struct PoolState {
+    keys: PoolKeys,
+    token_decimals: u8,
+    base_vault: u64,
+    quote_vault: u64,
+    /// Vault balances that belong to the protocol, from the pool state account
+    deductions: (u64, u64),
+    lp_supply: u64,
+    mint_authority: COption<Pubkey>,
+    freeze_authority: COption<Pubkey>,
+    slot: u64,
+    updated_at: Instant,
+}
+
+struct TrackedPool {
+    state: PoolState,
+    sender: watch::Sender<PoolSnapshot>,
+    /// When tracking stops, once the pool has been released
+    expires_at: Option<Instant>,
+}
+
+/// How often released pools are checked for expiry
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Capacity of the all-pools update feed
+const UPDATE_FEED_CAPACITY: usize = 1024;
+
+/// Capacity of the withdrawal feed
+const WITHDRAWAL_FEED_CAPACITY: usize = 64;
+
+/// Log message Anchor emits for the CPMM withdraw instruction
+const WITHDRAW_LOG: &str = "Instruction: Withdraw";
+
+impl PoolState {
+    fn snapshot(&self) -> PoolSnapshot {
+        let reserves = Reserves::from_sides(
+            &self.keys,
+            self.base_vault.saturating_sub(self.deductions.0),
+            self.quote_vault.saturating_sub(self.deductions.1),
+        );
+        PoolSnapshot {
+            slot: self.slot,
+            reserves,
+            price: reserves.price(self.token_decimals),
+            mint_authority: self.mint_authority,
+            freeze_authority: self.freeze_authority,
+            lp_supply: self.lp_supply,
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Apply an update to one of the pool's accounts; returns whether it was for this pool
+    fn apply(&mut self, address: &Pubkey, slot: u64, data: &[u8]) -> Result<bool> {
+        if slot < self.slot {
+            return Ok(false);
+        }
+
+        if *address == self.keys.base_vault {
+            self.base_vault = TokenAccount::unpack_from_slice(data)?.amount;
+        } else if *address == self.keys.quote_vault {
+            self.quote_vault = TokenAccount::unpack_from_slice(data)?.amount;
+        } else if *address == self.keys.pool {
+            self.deductions = pool::vault_deductions(self.keys.pool_type, data)?;
+            self.lp_supply = pool::lp_supply(self.keys.pool_type, data)?;
+        } else if *address == self.keys.token_mint() {
+            let mint = Mint::unpack_from_slice(data)?;
+            self.mint_authority = mint.mint_authority;
+            self.freeze_authority = mint.freeze_authority;
+        } else {
+            return Ok(false);
+        }
+
+        self.slot = slot;
+        self.updated_at = Instant::now();
+        Ok(true)
+    }
+}
+
+/// Keeps reserves of held and watched pools current from account subscriptions
+///
+/// Each tracked pool's vaults, state account and token mint are streamed from
+/// Geyser when configured, otherwise with `accountSubscribe`. Every update is
+/// published to the pool's watch channel, so readers get prices without RPC
+/// calls. Transactions that withdraw liquidity from a tracked pool are
+/// published on a separate feed; over PubSub they are recognised from logs,
+/// which only CPMM withdrawals can be.
+pub struct ReserveTracker {
+    rpc_client: Arc<RpcClient>,
+    geyser_url: Option<String>,
+    websocket_url: String,
+    pools: Mutex<HashMap<Pubkey, TrackedPool>>,
+    /// Signalled when pools are added or removed, so subscriptions are rebuilt
+    changed: Notify,
+    /// Every snapshot of every pool, for consumers that follow all tracked pools
+    updates: broadcast::Sender<(Pubkey, PoolSnapshot)>,
+    /// Liquidity withdrawals from tracked pools
+    withdrawals: broadcast::Sender<Withdrawal>,
+    /// How long a released pool stays tracked
+    history_window: Duration,
+}
+
+impl ReserveTracker {
+    pub fn new(rpc_client: Arc<RpcClient>, config: &Config) -> Self {
+        Self {
+            rpc_client,
+            geyser_url: config.yellowstone_grpc_url.clone(),
+            websocket_url: config
+                .websocket_url
+                .clone()
+                .unwrap_or_else(|| tpu::websocket_url_for(&config.rpc_url)),
+            pools: Mutex::new(HashMap::new()),
+            changed: Notify::new(),
+            updates: broadcast::channel(UPDATE_FEED_CAPACITY).0,
+            withdrawals: broadcast::channel(WITHDRAWAL_FEED_CAPACITY).0,
+            history_window: Duration::from_secs(config.price_history_secs),
+        }
+    }
+
+    /// Start tracking a pool (if not already) and subscribe to its snapshots
+    ///
+    /// The first snapshot is read over RPC, so the receiver always holds a value.
+    /// Tracking an already tracked pool cancels any pending release.
+    pub async fn track(&self, pool: &Pubkey, pool_type: PoolType) -> Result<watch::Receiver<PoolSnapshot>> {
+        if let Some(tracked) = self.pools.lock().unwrap().get_mut(pool) {
+            tracked.expires_at = None;
+            return Ok(tracked.sender.subscribe());
+        }
+
+        let keys = PoolKeys::fetch(&self.rpc_client, pool, pool_type).await?;
+        let (slot, accounts) = self.fetch_accounts(&keys).await?;
+        let account = |i: usize, name: &str| {
+            accounts[i]
+                .as_ref()
+                .with_context(|| format!("Pool {} {} not found", pool, name))
+        };
+
+        let mint = Mint::unpack_from_slice(&account(3, "token mint")?.data)
+            .context("Invalid token mint account")?;
+        let state = PoolState {
+            token_decimals: mint.decimals,
+            base_vault: TokenAccount::unpack_from_slice(&account(1, "base vault")?.data)
+                .context("Invalid pool vault account")?
+                .amount,
+            quote_vault: TokenAccount::unpack_from_slice(&account(2, "quote vault")?.data)
+                .context("Invalid pool vault account")?
+                .amount,
+            deductions: pool::vault_deductions(pool_type, &account(0, "state")?.data)?,
+            lp_supply: pool::lp_supply(pool_type, &account(0, "state")?.data)?,
+            mint_authority: mint.mint_authority,
+            freeze_authority: mint.freeze_authority,
+            slot,
+            updated_at: Instant::now(),
+            keys,
+        };
+        let snapshot = state.snapshot();
+        let (sender, receiver) = watch::channel(snapshot);
+
+        let mut pools = self.pools.lock().unwrap();
+        // Another caller may have started tracking while we were fetching
+        if let Some(existing) = pools.get(pool) {
+            return Ok(existing.sender.subscribe());
+        }
+        pools.insert(
+            *pool,
+            TrackedPool {
+                state,
+                sender,
+                expires_at: None,
+            },
+        );
+        drop(pools);
+
+        log::debug!("Tracking reserves of {}", pool);
+        let _ = self.updates.send((*pool, snapshot));
+        self.changed.notify_one();
+        Ok(receiver)
+    }
+
+    /// Read a pool's state account, vaults and token mint over RPC, with the slot they were read at
+    async fn fetch_accounts(&self, keys: &PoolKeys) -> Result<(u64, Vec<Option<Account>>)> {
+        let response = self
+            .rpc_client
+            .get_multiple_accounts_with_commitment(
+                &[keys.pool, keys.base_vault, keys.quote_vault, keys.token_mint()],
+                CommitmentConfig::processed(),
+            )
+            .await
+            .context("Failed to fetch pool accounts")?;
+        Ok((response.context.slot, response.value))
+    }
+
+    /// Re-read a tracked pool over RPC and publish the result
+    ///
+    /// For readers whose snapshot has gone stale, whether the pool is quiet or
+    /// its stream has stalled. Stream updates newer than the RPC read win.
+    pub async fn refresh(&self, pool: &Pubkey) -> Result<PoolSnapshot> {
+        let keys = self
+            .keys(pool)
+            .with_context(|| format!("Pool {} is not tracked", pool))?;
+        let (slot, accounts) = self.fetch_accounts(&keys).await?;
+        let addresses = [keys.pool, keys.base_vault, keys.quote_vault, keys.token_mint()];
+
+        let mut pools = self.pools.lock().unwrap();
+        let tracked = pools
+            .get_mut(pool)
+            .with_context(|| format!("Pool {} is no longer tracked", pool))?;
+        for (address, account) in addresses.iter().zip(&accounts) {
+            if let Some(account) = account {
+                tracked.state.apply(address, slot, &account.data)?;
+            }
+        }
+        tracked.state.updated_at = Instant::now();
+        Ok(self.publish(tracked))
+    }
+
+    /// Stop tracking a pool; its receivers keep the last snapshot
+    pub fn untrack(&self, pool: &Pubkey) {
+        if self.pools.lock().unwrap().remove(pool).is_some() {
+            log::debug!("Stopped tracking reserves of {}", pool);
+            self.changed.notify_one();
+        }
+    }
+
+    /// Keep tracking a pool for the price history window, then stop
+    pub fn release(&self, pool: &Pubkey) {
+        if let Some(tracked) = self.pools.lock().unwrap().get_mut(pool) {
+            tracked.expires_at = Some(Instant::now() + self.history_window);
+        }
+    }
+
+    /// Subscribe to snapshots of every tracked pool
+    pub fn updates(&self) -> broadcast::Receiver<(Pubkey, PoolSnapshot)> {
+        self.updates.subscribe()
+    }
+
+    /// Subscribe to liquidity withdrawals from every tracked pool
+    pub fn withdrawals(&self) -> broadcast::Receiver<Withdrawal> {
+        self.withdrawals.subscribe()
+    }
+
+    /// Subscribe to a pool that is already tracked
+    pub fn subscribe(&self, pool: &Pubkey) -> Option<watch::Receiver<PoolSnapshot>> {
+        self.pools
+            .lock()
+            .unwrap()
+            .get(pool)
+            .map(|tracked| tracked.sender.subscribe())
+    }
+
+    /// Decoded keys of a tracked pool
+    pub fn keys(&self, pool: &Pubkey) -> Option<PoolKeys> {
+        self.pools
+            .lock()
+            .unwrap()
+            .get(pool)
+            .map(|tracked| tracked.state.keys.clone())
+    }
+
+    /// Keep subscriptions in line with the tracked pools and publish their updates
+    pub async fn run(self: Arc<Self>) {
+        tokio::spawn(self.clone().prune_released());
+
+        loop {
+            let (pools, accounts) = self.tracked_accounts();
+            if pools.is_empty() {
+                self.changed.notified().await;
+                continue;
+            }
+
+            tokio::select! {
+                result = self.stream(&pools, &accounts) => {
+                    if let Err(e) = result {
+                        log::warn!("Reserve tracking interrupted, resubscribing: {}", e);
+                    }
+                    sleep(Duration::from_secs(1)).await;
+                }
+                _ = self.changed.notified() => {}
+            }
+        }
+    }
+
+    /// Stop tracking released pools whose history window has passed
+    async fn prune_released(self: Arc<Self>) {
+        loop {
+            sleep(PRUNE_INTERVAL).await;
+            let now = Instant::now();
+            let expired: Vec<Pubkey> = self
+                .pools
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, tracked)| tracked.expires_at.is_some_and(|at| at <= now))
+                .map(|(pool, _)| *pool)
+                .collect();
+            for pool in expired {
+                self.untrack(&pool);
+            }
+        }
+    }
+
+    /// Tracked pools, and every account of theirs that is streamed
+    fn tracked_accounts(&self) -> (Vec<Pubkey>, Vec<Pubkey>) {
+        let pools = self.pools.lock().unwrap();
+        let accounts = pools
+            .values()
+            .flat_map(|tracked| {
+                let keys = &tracked.state.keys;
+                [keys.pool, keys.base_vault, keys.quote_vault, keys.token_mint()]
+            })
+            .collect();
+        (pools.keys().copied().collect(), accounts)
+    }
+
+    /// Publish the current snapshot of a pool to its watchers and the update feed
+    fn publish(&self, tracked: &TrackedPool) -> PoolSnapshot {
+        let snapshot = tracked.state.snapshot();
+        tracked.sender.send_replace(snapshot);
+        // No receivers is fine
+        let _ = self.updates.send((tracked.state.keys.pool, snapshot));
+        snapshot
+    }
+
+    /// Apply an account update and publish the new snapshot of the pool it belongs to
+    fn apply(&self, address: &Pubkey, slot: u64, data: &[u8]) {
+        let mut pools = self.pools.lock().unwrap();
+        for tracked in pools.values_mut() {
+            match tracked.state.apply(address, slot, data) {
+                Ok(true) => {
+                    self.publish(tracked);
+                }
+                Ok(false) => {}
+                Err(e) => log::debug!("Ignoring update of {}: {}", address, e),
+            }
+        }
+    }
+
+    /// Publish a withdrawal if it is from a tracked pool
+    fn withdrawn(&self, pool: Pubkey, signature: String, lp_amount: Option<u64>) {
+        if self.pools.lock().unwrap().contains_key(&pool) {
+            let _ = self.withdrawals.send(Withdrawal {
+                pool,
+                signature,
+                lp_amount,
+            });
+        }
+    }
+
+    async fn stream(&self, pools: &[Pubkey], accounts: &[Pubkey]) -> Result<()> {
+        if let Some(ref grpc_url) = self.geyser_url {
+            match self.stream_geyser(grpc_url, pools, accounts).await {
+                Ok(()) => return Ok(()),
+                Err(e) => log::warn!("Geyser reserve stream failed, using WebSocket: {}", e),
+            }
+        }
+        self.stream_websocket(pools, accounts).await
+    }
+
+    /// Stream the accounts, and transactions touching the pools, over Geyser
+    async fn stream_geyser(&self, grpc_url: &str, pools: &[Pubkey], accounts: &[Pubkey]) -> Result<()> {
+        use yellowstone_grpc::{
+            geyser::SubscribeRequest,
+            proto::geyser::{
+                CommitmentLevel, SubscribeRequestFilterAccounts, SubscribeRequestFilterTransactions,
+            },
+        };
+
+        let mut client = yellowstone_grpc::GeyserGrpcClient::connect(grpc_url)
+            .await
+            .context("Failed to connect to Yellowstone Geyser")?;
+
+        let filter = SubscribeRequestFilterAccounts {
+            account: accounts.iter().map(Pubkey::to_string).collect(),
+            owner: vec![],
+            filters: vec![],
+        };
+        let transactions = SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            signature: None,
+            account_include: pools.iter().map(Pubkey::to_string).collect(),
+            account_exclude: vec![],
+            account_required: vec![],
+        };
+
+        // Processed: a withdrawal should be answered before it is even confirmed
+        let request = SubscribeRequest {
+            slots: vec![],
+            accounts: vec![filter],
+            transactions: vec![transactions],
+            transactions_status: vec![],
+            blocks: vec![],
+            blocks_meta: vec![],
+            accounts_data_slice: vec![],
+            commitment: Some(CommitmentLevel::Processed as i32),
+        };
+
+        let mut stream = client
+            .subscribe_once(request)
+            .await
+            .context("Failed to subscribe to Geyser stream")?;
+
+        while let Some(update) = stream.message().await.transpose() {
+            let update = update.context("Error receiving Geyser update")?;
+            if let Some(account_update) = update.account {
+                let slot = account_update.slot;
+                if let Some(info) = account_update.account {
+                    if let Ok(address) = Pubkey::try_from(info.pubkey.as_slice()) {
+                        self.apply(&address, slot, &info.data);
+                    }
+                }
+            }
+
+            if let Some(tx) = update.transaction.and_then(|update| update.transaction) {
+                let signature = Signature::try_from(tx.signature.as_slice())
+                    .map(|signature| signature.to_string())
+                    .unwrap_or_default();
+                let Some(message) = tx.transaction.and_then(|tx| tx.message) else {
+                    continue;
+                };
+                let keys: Vec<Pubkey> = message
+                    .account_keys
+                    .iter()
+                    .filter_map(|key| Pubkey::try_from(key.as_slice()).ok())
+                    .collect();
+
+                for ix in &message.instructions {
+                    let Some(program_id) = keys.get(ix.program_id_index as usize) else {
+                        continue;
+                    };
+                    let Some((index, lp_amount)) =
+                        instructions::liquidity_withdrawal(program_id, &ix.data)
+                    else {
+                        continue;
+                    };
+                    if let Some(pool) = ix.accounts.get(index).and_then(|i| keys.get(*i as usize)) {
+                        self.withdrawn(*pool, signature.clone(), Some(lp_amount));
+                    }
+                }
+            }
+        }
+
+        anyhow::bail!("Geyser stream ended")
+    }
+
+    /// Stream the accounts with `accountSubscribe` and pool logs with `logsSubscribe`
+    async fn stream_websocket(&self, pools: &[Pubkey], accounts: &[Pubkey]) -> Result<()> {
+        let client = PubsubClient::new(&self.websocket_url)
+            .await
+            .context("Failed to connect to PubSub")?;
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::processed()),
+            ..Default::default()
+        };
+
+        let mut streams = Vec::new();
+        for address in accounts.iter().copied() {
+            let (stream, _unsubscribe) = client
+                .account_subscribe(&address, Some(config.clone()))
+                .await
+                .with_context(|| format!("accountSubscribe failed for {}", address))?;
+            streams.push(
+                stream
+                    .filter_map(move |response| async move {
+                        let data = response.value.data.decode()?;
+                        Some(Notice::Account {
+                            address,
+                            slot: response.context.slot,
+                            data,
+                        })
+                    })
+                    .boxed(),
+            );
+        }
+        for pool in pools.iter().copied() {
+            let (stream, _unsubscribe) = client
+                .logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![pool.to_string()]),
+                    RpcTransactionLogsConfig {
+                        commitment: Some(CommitmentConfig::processed()),
+                    },
+                )
+                .await
+                .with_context(|| format!("logsSubscribe failed for {}", pool))?;
+            streams.push(
+                stream
+                    .filter_map(move |response| async move {
+                        let logs = response.value;
+                        let withdrawn = logs.err.is_none() && cpmm_withdraw_logged(&logs.logs);
+                        withdrawn.then_some(Notice::Withdrawal {
+                            pool,
+                            signature: logs.signature,
+                        })
+                    })
+                    .boxed(),
+            );
+        }
+
+        let mut notices = select_all(streams);
+        while let Some(notice) = notices.next().await {
+            match notice {
+                Notice::Account { address, slot, data } => self.apply(&address, slot, &data),
+                Notice::Withdrawal { pool, signature } => self.withdrawn(pool, signature, None),
+            }
+        }
+
+        anyhow::bail!("PubSub subscriptions closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn cpmm_withdraw_is_recognised() {
+        let cpmm = RAYDIUM_CPMM_PROGRAM_ID;
+        assert!(cpmm_withdraw_logged(&logs(&[
+            &format!("Program {} invoke [1]", cpmm),
+            "Program log: Instruction: Withdraw",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+            &format!("Program {} success", cpmm),
+        ])));
+    }
+
+    #[test]
+    fn withdraw_of_another_anchor_program_is_ignored() {
+        let cpmm = RAYDIUM_CPMM_PROGRAM_ID;
+        assert!(!cpmm_withdraw_logged(&logs(&[
+            "Program Stake11111111111111111111111111111111111111 invoke [1]",
+            "Program log: Instruction: Withdraw",
+            "Program Stake11111111111111111111111111111111111111 success",
+            &format!("Program {} invoke [1]", cpmm),
+            "Program log: Instruction: SwapBaseInput",
+            &format!("Program {} success", cpmm),
+        ])));
+    }
+
+    #[test]
+    fn withdraw_logged_by_a_program_cpmm_invokes_is_ignored() {
+        let cpmm = RAYDIUM_CPMM_PROGRAM_ID;
+        assert!(!cpmm_withdraw_logged(&logs(&[
+            &format!("Program {} invoke [1]", cpmm),
+            "Program Farm111111111111111111111111111111111111111 invoke [2]",
+            "Program log: Instruction: Withdraw",
+            "Program Farm111111111111111111111111111111111111111 success",
+            &format!("Program {} success", cpmm),
+        ])));
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::confirm::ConfirmationSource;
+use crate::detector::{PoolCreationEvent, PoolType};
+use crate::exits::ExitReason;
+use crate::fills::Fill;
+use crate::positions::Position;
+use crate::scoring::ScoreCard;
+use crate::sniper::{BuyReceipt, SellReceipt};
+
+/// A buy attempt as persisted in the trade log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub timestamp: i64,
+    pub pool: String,
+    pub pool_type: PoolType,
+    pub creator: String,
+    pub signature: String,
+    pub amount_sol: f64,
+    pub dry_run: bool,
+    pub score: ScoreCard,
+    /// Time from first send to the target commitment
+    #[serde(default)]
+    pub confirmation_ms: Option<u64>,
+    #[serde(default)]
+    pub confirmed_via: Option<ConfirmationSource>,
+    /// Tokens received and SOL actually spent, parsed from the landed transaction
+    #[serde(default)]
+    pub fill: Option<Fill>,
+}
+
+impl TradeRecord {
+    pub fn new(
+        event: &PoolCreationEvent,
+        receipt: &BuyReceipt,
+        amount_sol: f64,
+        dry_run: bool,
+        score: ScoreCard,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            pool: event.pool.to_string(),
+            pool_type: event.pool_type,
+            creator: event.creator.to_string(),
+            signature: receipt.signature.clone(),
+            amount_sol,
+            dry_run,
+            score,
+            confirmation_ms: receipt
+                .confirmation
+                .map(|(latency, _)| latency.as_millis() as u64),
+            confirmed_via: receipt.confirmation.map(|(_, source)| source),
+            fill: receipt.fill.clone(),
+        }
+    }
+}
+
+/// A sell as persisted in the trade log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SellRecord {
+    pub timestamp: i64,
+    pub mint: String,
+    pub pool: String,
+    pub signature: String,
+    /// Raw token amount sold
+    pub token_amount: u64,
+    /// SOL the pre-trade quote expected, in lamports
+    pub quoted_lamports: u64,
+    /// Why the position was exited
+    pub reason: ExitReason,
+    pub dry_run: bool,
+    /// SOL actually received, parsed from the landed transaction
+    pub fill: Option<Fill>,
+}
+
+impl SellRecord {
+    pub fn new(
+        position: &Position,
+        receipt: &SellReceipt,
+        token_amount: u64,
+        reason: ExitReason,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            mint: position.mint.clone(),
+            pool: position.pool.clone(),
+            signature: receipt.signature.clone(),
+            token_amount,
+            quoted_lamports: receipt.quoted_lamports,
+            reason,
+            dry_run,
+            fill: receipt.fill.clone(),
+        }
+    }
+}
+
+/// Append-only JSON-lines trade log
+pub struct TradeLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl TradeLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Append a record to the log
+    pub fn append<T: Serialize>(&self, record: &T) -> Result<()> {
+        let line = serde_json::to_string(record)?;
+        let _guard = self.lock.lock().unwrap();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open trade log {}", self.path.display()))?;
+        writeln!(file, "{}", line).context("Failed to write trade log")?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::LevelFilter;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
 
 /// Initialize logging based on log level string
 pub fn init_logging(log_level: &str) -> Result<()> {
@@ -21,6 +25,58 @@ pub fn init_logging(log_level: &str) -> Result<()> {
     Ok(())
 }
 
+/// A store kept in memory and written to disk in the background
+///
+/// Changes only set the dirty flag; `run_persist` writes them, so recording
+/// something never touches the disk on a hot path.
+pub trait Persisted: Send + Sync + 'static {
+    /// What the store is called in log messages
+    const NAME: &'static str;
+
+    /// Set when the store changes, cleared when it is written
+    fn dirty(&self) -> &AtomicBool;
+
+    /// Write the whole store to disk
+    fn save(&self) -> Result<()>;
+
+    /// Mark the store as changed since the last write
+    fn mark_dirty(&self) {
+        self.dirty().store(true, Ordering::Release);
+    }
+}
+
+/// Write a store if it changed since the last write, off the async runtime
+pub async fn flush<T: Persisted>(store: &Arc<T>) {
+    if !store.dirty().swap(false, Ordering::AcqRel) {
+        return;
+    }
+
+    let writer = store.clone();
+    let result = tokio::task::spawn_blocking(move || writer.save())
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    if let Err(e) = result {
+        log::warn!("Failed to save {}: {}", T::NAME, e);
+        store.mark_dirty();
+    }
+}
+
+/// Write a store's changes every `interval`
+pub async fn run_persist<T: Persisted>(store: Arc<T>, interval: Duration) {
+    loop {
+        sleep(interval).await;
+        flush(&store).await;
+    }
+}
+
+/// Write `data` to `path` through a temporary file, so a crash never leaves it half-written
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
 /// Format lamports to SOL
 pub fn lamports_to_sol(lamports: u64) -> f64 {
     lamports as f64 / 1_000_000_000.0
@@ -36,21 +92,6 @@ pub async fn rate_limit_delay(ms: u64) {
     tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
 }
 
-/// Estimate priority fee dynamically
-/// 
-/// In production, you should query get_recent_prioritization_fees
-pub async fn estimate_priority_fee(
-    _rpc_client: &solana_client::nonblocking::rpc_client::RpcClient,
-    base_fee_micro_lamports: u64,
-) -> u64 {
-    // Simple heuristic - in production, query recent fees
-    // For now, return base fee with some jitter
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let jitter = rng.gen_range(0..50_000);
-    base_fee_micro_lamports + jitter
-}
-
 /// Calculate minimum amount out with slippage
 pub fn calculate_min_amount_out(amount_out: u64, slippage_bps: u16) -> u64 {
     let slippage_factor = (10000 - slippage_bps) as f64 / 10000.0;